@@ -0,0 +1,48 @@
+use advent2022::day3;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const LINES: usize = 1_000_000;
+const GROUP_SIZE: usize = 3;
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Rucksacks in groups of three, each group sharing exactly one badge. Only whole
+/// groups are made, so `lines` is rounded down to a multiple of the group size.
+fn generate(lines: usize) -> String {
+    let mut state: u64 = 2022;
+    let mut next = move |below: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % below as u64) as usize
+    };
+    let mut input = String::with_capacity(lines * 25);
+    for _ in 0..lines / GROUP_SIZE {
+        let badge = ITEMS[next(ITEMS.len())];
+        let others: Vec<u8> = ITEMS.iter().copied().filter(|&c| c != badge).collect();
+        // Each member of the group draws from its own third of the other items
+        for pool in others.chunks(others.len() / GROUP_SIZE) {
+            let mut ruck: Vec<u8> = (0..23).map(|_| pool[next(pool.len())]).collect();
+            ruck.insert(next(24), badge);
+            input.push_str(std::str::from_utf8(&ruck).unwrap());
+            input.push('\n');
+        }
+    }
+    input
+}
+
+fn rucksacks(c: &mut Criterion) {
+    let input = generate(LINES);
+    let mut group = c.benchmark_group("day3");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(10);
+    group.bench_function("part 1, a million rucksacks", |b| {
+        b.iter(|| day3::part1_main(&mut input.as_bytes()).unwrap())
+    });
+    group.bench_function("part 2, a million rucksacks", |b| {
+        b.iter(|| day3::part2_main(&mut input.as_bytes(), GROUP_SIZE).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rucksacks);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+static FRAME_DELAY: OnceLock<Duration> = OnceLock::new();
+
+/// Turn on animation; until this is called every `frame` is a no-op
+pub fn init(delay: Duration) {
+    FRAME_DELAY.set(delay).expect("Animation already initialised");
+}
+
+pub fn enabled() -> bool {
+    FRAME_DELAY.get().is_some()
+}
+
+/// Redraw the terminal with `scene`, then wait for the configured delay.
+/// Frames go to stderr so that stdout keeps only the answer.
+pub fn frame(title: &str, scene: &impl fmt::Display) {
+    if let Some(delay) = FRAME_DELAY.get() {
+        let mut err = io::stderr().lock();
+        _ = write!(err, "{}{}\n{}\n", CLEAR_SCREEN, title, scene);
+        _ = err.flush();
+        thread::sleep(*delay);
+    }
+}
@@ -1,87 +1,407 @@
-use std::cmp::Reverse;
+use crate::parse::{self, Lines, ParseError};
+use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
-use std::fs;
-use std::io::{BufRead, BufReader, Lines};
+use std::io::BufRead;
 
-struct InventoryFile {
-    lines: Lines<BufReader<fs::File>>,
-    completed: bool,
+/// Calorie totals per elf, one elf per blank-line separated group.
+/// When lenient, any line that isn't a number also separates groups.
+struct InventoryFile<R> {
+    lines: Lines<R>,
+    lenient: bool,
 }
 
-impl InventoryFile {
-    fn new(file: fs::File) -> InventoryFile {
+impl<R: BufRead> InventoryFile<R> {
+    fn new(reader: R, lenient: bool) -> InventoryFile<R> {
         InventoryFile {
-            lines: BufReader::new(file).lines(),
-            completed: false,
+            lines: parse::lines(reader),
+            lenient,
         }
     }
 }
 
-impl Iterator for InventoryFile {
-    type Item = Option<usize>;
+impl<R: BufRead> Iterator for InventoryFile<R> {
+    type Item = Result<usize, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(res) = self.lines.next() {
-            if let Ok(line) = res {
-                if let Ok(i) = line.parse::<usize>() {
-                    return Some(Some(i));
-                } else {
-                    return Some(None);
+        let mut count: usize = 0;
+        let mut items: usize = 0;
+        let mut malformed: Vec<ParseError> = Vec::new();
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if line.is_blank() {
+                if items > 0 || !malformed.is_empty() {
+                    break;
                 }
+                continue;
+            }
+            match line.parse::<usize>(&line.text) {
+                Ok(i) => match count.checked_add(i) {
+                    Some(total) => {
+                        count = total;
+                        items += 1;
+                    }
+                    None => malformed.push(line.error_at(
+                        &line.text,
+                        format!("calorie total overflows after adding {}", i),
+                    )),
+                },
+                Err(_) if self.lenient => {
+                    log::warn!(
+                        "line {}: treating `{}` as a separator",
+                        line.number,
+                        line.text
+                    );
+                    if items > 0 {
+                        break;
+                    }
+                }
+                Err(e) => malformed.push(e),
             }
         }
-
-        if !self.completed {
-            self.completed = true;
-            Some(None)
+        if !malformed.is_empty() {
+            Some(Err(ParseError::combine(malformed)))
+        } else if items > 0 {
+            Some(Ok(count))
         } else {
             None
         }
     }
 }
 
-#[allow(dead_code)]
-fn top_calories(inv: &mut InventoryFile) -> usize {
-    let mut count: usize = 0;
-    let mut max_found: usize = 0;
-    inv.into_iter().for_each(|line| match line {
-        Some(i) => {
-            count += i;
-            max_found = max_found.max(count);
+const HISTOGRAM_BINS: usize = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+#[derive(clap::Args, Debug)]
+#[group(id = "day1")]
+#[command(next_help_heading = "Day 1")]
+pub struct Options {
+    /// Report the N best-stocked elves [default: 1 for part 1, 3 for part 2]
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    top: Option<usize>,
+
+    /// Also report statistics and a histogram of the per-elf calorie totals
+    #[arg(long)]
+    stats: bool,
+
+    /// Treat any line that isn't a number as a separator between elves
+    #[arg(long)]
+    lenient: bool,
+
+    /// Inventory files to combine, in place of the day's input
+    #[arg(long = "inventory", value_name = "FILE", num_args = 1..)]
+    inventories: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Elf {
+    // index of the inventory the elf was listed in
+    source: usize,
+    // 1-based position of the elf's group in that inventory
+    number: usize,
+    calories: usize,
+}
+
+impl PartialOrd for Elf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Elf {
+    // more calories is better; on a tie the elf listed first wins
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.calories
+            .cmp(&other.calories)
+            .then_with(|| (other.source, other.number).cmp(&(self.source, self.number)))
+    }
+}
+
+fn read_elves<R: BufRead>(reader: R, source: usize, lenient: bool) -> Result<Vec<Elf>, ParseError> {
+    let totals = parse::collect_all(InventoryFile::new(reader, lenient))?;
+    Ok(totals
+        .into_iter()
+        .enumerate()
+        .map(|(idx, calories)| Elf {
+            source,
+            number: idx + 1,
+            calories,
+        })
+        .collect())
+}
+
+fn default_top(part: u8) -> usize {
+    if part == 1 {
+        1
+    } else {
+        3
+    }
+}
+
+/// The `n` elves carrying the most calories, best stocked first
+fn top_elves<'a>(elves: impl IntoIterator<Item = &'a Elf>, n: usize) -> Vec<Elf> {
+    let mut most_calories: BinaryHeap<Reverse<Elf>> = BinaryHeap::with_capacity(n + 1);
+    for &elf in elves {
+        most_calories.push(Reverse(elf));
+        if most_calories.len() > n {
+            _ = most_calories.pop();
         }
-        None => {
-            count = 0;
+    }
+    most_calories
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(elf)| elf)
+        .collect()
+}
+
+/// Calories carried between the elves, widened as a few elves that each carry
+/// up to `usize::MAX` can together carry more
+fn total_calories(elves: &[Elf]) -> u128 {
+    elves.iter().map(|elf| elf.calories as u128).sum()
+}
+
+#[derive(Debug, PartialEq)]
+struct Statistics {
+    mean: f64,
+    median: f64,
+    std_dev: f64,
+}
+
+fn statistics(totals: &[usize]) -> Option<Statistics> {
+    if totals.is_empty() {
+        return None;
+    }
+    let count = totals.len() as f64;
+    // widened for the same reason as `total_calories`
+    let sum: u128 = totals.iter().map(|&total| total as u128).sum();
+    let mean = sum as f64 / count;
+    let variance = totals
+        .iter()
+        .map(|&t| (t as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let mut sorted = totals.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] as f64 + sorted[middle] as f64) / 2.0
+    } else {
+        sorted[middle] as f64
+    };
+    Some(Statistics {
+        mean,
+        median,
+        std_dev: variance.sqrt(),
+    })
+}
+
+/// Per-elf totals bucketed into equal-width bins between the smallest and largest,
+/// with no more bins than it takes to cover that range
+fn histogram(totals: &[usize]) -> String {
+    let (Some(&min), Some(&max)) = (totals.iter().min(), totals.iter().max()) else {
+        return String::new();
+    };
+    let width = (max - min) / HISTOGRAM_BINS + 1;
+    let mut counts = vec![0usize; (max - min) / width + 1];
+    for &total in totals {
+        counts[(total - min) / width] += 1;
+    }
+    let tallest = counts.iter().copied().max().unwrap_or(1);
+    let label_width = max.to_string().len();
+    let mut chart = String::new();
+    for (bin, &count) in counts.iter().enumerate() {
+        let low = min + bin * width;
+        let high = low.saturating_add(width - 1).min(max);
+        chart.push_str(&format!("{:>w$} - {:>w$} |", low, high, w = label_width));
+        if count > 0 {
+            let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(tallest));
+            chart.push_str(&format!(" {}", bar));
         }
-    });
-    max_found
+        chart.push_str(&format!(" {}\n", count));
+    }
+    chart
 }
 
-fn top_3_calories(inv: &mut InventoryFile) -> usize {
-    let mut most_calories: BinaryHeap<Reverse<usize>> = BinaryHeap::with_capacity(4);
-    let mut count: usize = 0;
-    inv.into_iter().for_each(|line| match line {
-        Some(i) => {
-            count += i;
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    let elves = read_elves(input, 0, false)?;
+    Ok(total_calories(&top_elves(&elves, default_top(part))).to_string())
+}
+
+fn list_elves(elves: &[Elf], sources: Option<&[&str]>) -> String {
+    let listed: Vec<String> = elves
+        .iter()
+        .map(|elf| match sources {
+            Some(sources) => format!("{} #{} ({})", sources[elf.source], elf.number, elf.calories),
+            None => format!("#{} ({})", elf.number, elf.calories),
+        })
+        .collect();
+    listed.join(", ")
+}
+
+pub fn day_main(filename: &str, part: u8, options: &Options) -> Result<(), ParseError> {
+    let sources: Vec<&str> = if options.inventories.is_empty() {
+        vec![filename]
+    } else {
+        options.inventories.iter().map(String::as_str).collect()
+    };
+    let mut elves: Vec<Elf> = Vec::new();
+    let mut errors: Vec<ParseError> = Vec::new();
+    for (source, path) in sources.iter().enumerate() {
+        match parse::open(path).and_then(|f| read_elves(f, source, options.lenient)) {
+            Ok(found) => elves.extend(found),
+            Err(e) => errors.push(e.in_file(path)),
         }
-        None => {
-            most_calories.push(Reverse(count));
-            if most_calories.len() > 3 {
-                _ = most_calories.pop();
-            }
-            count = 0;
+    }
+    if !errors.is_empty() {
+        return Err(ParseError::combine(errors));
+    }
+
+    let n = options.top.unwrap_or_else(|| default_top(part));
+    let top = top_elves(&elves, n);
+    if sources.len() > 1 {
+        for (source, path) in sources.iter().enumerate() {
+            let own = top_elves(elves.iter().filter(|elf| elf.source == source), n);
+            let calories = total_calories(&own);
+            println!("{}: {} (total {})", path, list_elves(&own, None), calories);
         }
-    });
-    most_calories.iter().fold(0, |total, i| total + i.0)
+        println!(
+            "Best-stocked elves overall: {}",
+            list_elves(&top, Some(&sources))
+        );
+    } else {
+        println!("Best-stocked elves: {}", list_elves(&top, None));
+    }
+    println!("The most calories available: {}", total_calories(&top));
+
+    if options.stats {
+        let totals: Vec<usize> = elves.iter().map(|elf| elf.calories).collect();
+        if let Some(stats) = statistics(&totals) {
+            println!("Elves: {}", totals.len());
+            println!("Mean: {:.1}", stats.mean);
+            println!("Median: {:.1}", stats.median);
+            println!("Standard deviation: {:.1}", stats.std_dev);
+            print!("{}", histogram(&totals));
+        }
+    }
+    Ok(())
 }
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = fs::File::open(filename).expect("couldn't open file");
-    let mut inv = InventoryFile::new(f);
-    let most_calories: usize;
-    if part == 1 {
-        most_calories = top_calories(&mut inv);
-    } else {
-        most_calories = top_3_calories(&mut inv);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTALS: [usize; 5] = [6000, 4000, 11000, 24000, 10000];
+
+    const TYPO: &str = "1000\n12a4\n2000\n\n3000\n";
+
+    #[test]
+    fn strict_reports_malformed_lines() {
+        match parse::collect_all(InventoryFile::new(TYPO.as_bytes(), false)) {
+            Err(ParseError::Malformed { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_overflowing_totals() {
+        let input = format!("{}\n1\n\n{}\n", usize::MAX, usize::MAX);
+        match parse::collect_all(InventoryFile::new(input.as_bytes(), false)) {
+            Err(ParseError::Malformed { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        let elves = elves(0, &[usize::MAX, usize::MAX]);
+        assert_eq!(total_calories(&elves), 2 * usize::MAX as u128);
+        let stats = statistics(&[usize::MAX, usize::MAX]).unwrap();
+        assert_eq!(stats.mean, usize::MAX as f64);
+        assert_eq!(stats.median, usize::MAX as f64);
+    }
+
+    #[test]
+    fn lenient_splits_on_malformed_lines() {
+        let totals = parse::collect_all(InventoryFile::new(TYPO.as_bytes(), true)).unwrap();
+        assert_eq!(totals, vec![1000, 2000, 3000]);
+    }
+
+    fn elves(source: usize, totals: &[usize]) -> Vec<Elf> {
+        totals
+            .iter()
+            .enumerate()
+            .map(|(idx, &calories)| Elf {
+                source,
+                number: idx + 1,
+                calories,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn top_elves_by_number() {
+        let numbers: Vec<usize> = top_elves(&elves(0, &TOTALS), 3)
+            .iter()
+            .map(|elf| elf.number)
+            .collect();
+        assert_eq!(numbers, vec![4, 3, 5]);
+    }
+
+    #[test]
+    fn ties_favour_earlier_elves() {
+        let top = top_elves(&elves(0, &[5, 7, 5, 5]), 2);
+        assert_eq!(
+            top[1],
+            Elf {
+                source: 0,
+                number: 1,
+                calories: 5
+            }
+        );
+    }
+
+    #[test]
+    fn top_elves_across_sources() {
+        let mut all = elves(0, &TOTALS);
+        all.extend(elves(1, &[12000, 30000]));
+        let found: Vec<(usize, usize)> = top_elves(&all, 3)
+            .iter()
+            .map(|elf| (elf.source, elf.number))
+            .collect();
+        assert_eq!(found, vec![(1, 2), (0, 4), (1, 1)]);
+    }
+
+    #[test]
+    fn histogram_bins_stop_at_the_largest_total() {
+        assert_eq!(
+            histogram(&[5, 5, 5]),
+            format!("5 - 5 | {} 3\n", "#".repeat(40))
+        );
+        let chart = histogram(&[1, 4]);
+        assert_eq!(
+            chart,
+            format!(
+                "1 - 1 | {} 1\n2 - 2 | 0\n3 - 3 | 0\n4 - 4 | {} 1\n",
+                "#".repeat(40),
+                "#".repeat(40)
+            )
+        );
+        let chart = histogram(&[0, 25]);
+        assert!(
+            chart.ends_with("24 - 25 | ######################################## 1\n"),
+            "{}",
+            chart
+        );
+        assert!(histogram(&[0, usize::MAX]).contains(&usize::MAX.to_string()));
+    }
+
+    #[test]
+    fn summary_statistics() {
+        let stats = statistics(&TOTALS).unwrap();
+        assert_eq!(stats.mean, 11000.0);
+        assert_eq!(stats.median, 10000.0);
+        assert!((stats.std_dev - 6985.7).abs() < 0.1);
     }
-    println!("The most calories available: {:?}", most_calories);
 }
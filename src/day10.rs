@@ -1,58 +1,173 @@
-use std::io::{BufRead, BufReader};
+use crate::animate;
+use crate::parse::{self, Line, ParseError};
+use crate::trace;
+use serde_json::json;
+use std::fmt;
+use std::io::BufRead;
 
 const IMPORTANT_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
 
-pub fn day_main(filename: &str, part: u8) {
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
     let mut register: i64 = 1;
     let mut cycles: Vec<i64> = Vec::with_capacity(256);
-    let f = std::fs::File::open(filename).expect("Could not open file");
-    for line_ in BufReader::new(f).lines() {
-        if let Ok(line) = line_ {
-            let mut parts = line.split_ascii_whitespace();
-            match parts.next() {
-                Some("addx") => {
-                    cycles.push(register);
-                    cycles.push(register);
-                    let value: i64 = parts.next().unwrap().parse().unwrap();
-                    register += value;
-                }
-                Some("noop") => {
-                    cycles.push(register);
-                }
-                Some(s) => {
-                    eprintln!("Unknown instruction {}", s);
-                }
-                _ => (),
+    // the line of the instruction running during each cycle
+    let mut cycle_lines: Vec<usize> = Vec::with_capacity(256);
+    let mut last: Option<Line> = None;
+    for line in parse::lines(input) {
+        let line = last.insert(line?);
+        let mut parts = line.text.split_ascii_whitespace();
+        let first_cycle = cycles.len();
+        match parts.next() {
+            Some("addx") => {
+                cycles.push(register);
+                cycles.push(register);
+                let field = line.next_field(&mut parts, "addx value")?;
+                let value: i64 = line.parse(field)?;
+                register = register.checked_add(value).ok_or_else(|| {
+                    line.error_at(field, format!("the register grows past {}", i64::MAX))
+                })?;
+            }
+            Some("noop") => {
+                cycles.push(register);
+            }
+            Some(s) => {
+                return Err(line.error_at(s, format!("unknown instruction `{}`", s)));
+            }
+            _ => (),
+        }
+        cycle_lines.resize(cycles.len(), line.number);
+        if trace::enabled() {
+            for (cycle, x) in cycles.iter().enumerate().skip(first_cycle) {
+                trace::record(&line.text, &json!({ "cycle": cycle + 1, "x": x }));
             }
         }
     }
     match part {
-        1 => part1_main(&cycles),
-        2 => part2_main(&cycles),
+        1 => {
+            let needed = IMPORTANT_CYCLES[IMPORTANT_CYCLES.len() - 1];
+            if cycles.len() < needed {
+                let message = format!(
+                    "the program ends after {} cycles, but signal strengths are needed up to cycle {}",
+                    cycles.len(),
+                    needed
+                );
+                return Err(match last {
+                    Some(line) => line.error_at_end(message),
+                    None => ParseError::at_line(1, message),
+                });
+            }
+            part1_main(&cycles)
+                .map(|sum| sum.to_string())
+                .map_err(|cycle| {
+                    ParseError::at_line(
+                        cycle_lines[cycle - 1],
+                        format!("the signal strengths overflow at cycle {}", cycle),
+                    )
+                })
+        }
+        2 => Ok(part2_main(&cycles)),
         _ => panic!("unimplemented"),
     }
 }
 
-fn part2_main(cycles: &Vec<i64>) {
-    for row in 0..6 {
-        for column in 0..40 {
-            let idx: usize = 40 * row + column;
-            if (column as i64).abs_diff(cycles[idx]) <= 1 {
-                print!("#");
-            } else {
-                print!(" ");
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    let answer = solve(&mut parse::open(filename)?, part)?;
+    if part == 1 {
+        println!("sum: {}", answer);
+    } else {
+        print!("{}", answer);
+    }
+    Ok(())
+}
+
+/// The pixels the beam has drawn so far
+#[derive(Default)]
+struct Crt {
+    lit: Vec<bool>,
+}
+
+impl Crt {
+    fn draw(&mut self, sprite: i64) {
+        let column = self.lit.len() % CRT_WIDTH;
+        self.lit.push((column as i64).abs_diff(sprite) <= 1);
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..CRT_HEIGHT {
+            for column in 0..CRT_WIDTH {
+                let pixel = match self.lit.get(CRT_WIDTH * row + column) {
+                    Some(true) => '#',
+                    Some(false) => ' ',
+                    None => '.',
+                };
+                write!(f, "{}", pixel)?;
             }
+            writeln!(f)?;
         }
-        println!("");
+        Ok(())
     }
 }
 
-fn part1_main(cycles: &Vec<i64>) {
+fn part2_main(cycles: &[i64]) -> String {
+    let mut crt = Crt::default();
+    for (idx, &sprite) in cycles.iter().take(CRT_WIDTH * CRT_HEIGHT).enumerate() {
+        crt.draw(sprite);
+        animate::frame(&format!("Cycle {}, sprite at {}", idx + 1, sprite), &crt);
+    }
+    crt.to_string()
+}
+
+/// The sum of the signal strengths, or the cycle at which it grows too large
+fn part1_main(cycles: &[i64]) -> Result<i64, usize> {
     let mut sum: i64 = 0;
     for &ic in IMPORTANT_CYCLES.iter() {
-        let cycle_value = (ic as i64) * cycles[ic - 1];
-        println!("{} * {} = {}", ic, cycles[ic - 1], cycle_value);
-        sum += cycle_value;
+        let cycle_value = (ic as i64).checked_mul(cycles[ic - 1]).ok_or(ic)?;
+        log::debug!("{} * {} = {}", ic, cycles[ic - 1], cycle_value);
+        sum = sum.checked_add(cycle_value).ok_or(ic)?;
+    }
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_overflows() {
+        let input = format!("addx {}\naddx 1\n", i64::MAX - 1);
+        match solve(&mut input.as_bytes(), 2) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (2, 6)),
+            other => panic!("unexpected {:?}", other),
+        }
+        let input = format!("addx {}\n{}", i64::MAX / 2, "noop\n".repeat(220));
+        match solve(&mut input.as_bytes(), 1) {
+            Err(ParseError::Malformed { line, .. }) => assert_eq!(line, 19),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn draws_the_beam_so_far() {
+        let mut crt = Crt::default();
+        for sprite in [1, 1, 1, 5] {
+            crt.draw(sprite);
+        }
+        let drawn = crt.to_string();
+        let rows: Vec<&str> = drawn.lines().collect();
+        assert_eq!(rows.len(), CRT_HEIGHT);
+        assert_eq!(rows[0], format!("### {}", ".".repeat(CRT_WIDTH - 4)));
+        assert!(rows[1..].iter().all(|row| *row == ".".repeat(CRT_WIDTH)));
+    }
+
+    #[test]
+    fn wraps_the_beam_onto_the_next_row() {
+        let cycles = vec![1; CRT_WIDTH + 2];
+        let drawn = part2_main(&cycles);
+        let rows: Vec<&str> = drawn.lines().collect();
+        assert_eq!(rows[1], format!("##{}", ".".repeat(CRT_WIDTH - 2)));
     }
-    println!("sum: {}", sum);
 }
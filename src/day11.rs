@@ -1,40 +1,62 @@
+use crate::parse::{self, Line, ParseError};
+use crate::trace;
 use regex::Regex;
+use serde_json::json;
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 type Item = usize;
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = File::open(filename).expect("Unable to open file");
-    let mut monkeys = create_monkeys(f);
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    let mut monkeys = create_monkeys(input)?;
     let mut counts: Vec<usize> = vec![0; monkeys.len()];
     for (i, monkey) in monkeys.iter().enumerate() {
-        println!("{}: {:?}", i, monkey);
+        log::debug!("{}: {:?}", i, monkey);
     }
     let (rounds, destresser) = match part {
         1 => (20, StressManager::Divide(3)),
-        2 => (10000, StressManager::Remainder(monkey_divisor(&monkeys))),
+        2 => (10000, StressManager::Remainder(monkey_divisor(&monkeys)?)),
         _ => panic!("Not implemented"),
     };
-    for _ in 0..rounds {
+    for round in 1..=rounds {
         for i in 0..monkeys.len() {
-            while monkeys[i].items.len() > 0 {
-                let (to_idx, val) = monkeys[i].inspect_item(&destresser);
+            while !monkeys[i].items.is_empty() {
+                let (to_idx, val) = monkeys[i].inspect_item(&destresser)?;
                 counts[i] += 1;
                 monkeys[to_idx].items.push_back(val);
             }
         }
+        if trace::enabled() {
+            let items: Vec<&VecDeque<Item>> = monkeys.iter().map(|m| &m.items).collect();
+            let state = json!({ "items": items, "inspections": counts });
+            trace::record(&format!("round {}", round), &state);
+        }
     }
     counts.sort();
-    println!("{:?}", counts);
-    let most = counts.pop().unwrap();
-    let next_most = counts.pop().unwrap();
-    println!("{}", most * next_most);
+    log::debug!("Inspection counts: {:?}", counts);
+    // widened, as two counts can each be larger than the square root of `usize::MAX`
+    let most = counts.pop().unwrap() as u128;
+    let next_most = counts.pop().unwrap() as u128;
+    Ok((most * next_most).to_string())
+}
+
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    let monkey_business = solve(&mut parse::open(filename)?, part)?;
+    println!("{}", monkey_business);
+    Ok(())
 }
 
-fn monkey_divisor(monkeys: &Vec<Monkey>) -> usize {
-    monkeys.iter().map(|m| m.quotient).fold(1, |x, y| x * y)
+/// A common multiple of every monkey's test, so worry levels can be kept below it
+fn monkey_divisor(monkeys: &[Monkey]) -> Result<usize, ParseError> {
+    monkeys.iter().try_fold(1, |product: Item, m| {
+        product.checked_mul(m.quotient).ok_or_else(|| {
+            let line = &m.lines[3];
+            line.error_at_end(format!(
+                "the product of every monkey's divisor grows past {}",
+                Item::MAX
+            ))
+        })
+    })
 }
 
 enum StressManager {
@@ -51,67 +73,110 @@ impl StressManager {
     }
 }
 
+/// The most items the monkeys may start with, far beyond any real puzzle, as each
+/// is inspected up to once per monkey in every one of part 2's rounds
+const MAX_ITEMS: usize = 10_000;
+
 const OPERATION_LINE: &str = r"Operation: new = old (.) (\d+|old)";
 const QUOTIENT_LINE: &str = r"Test: divisible by (\d+)";
 const IF_TRUE_LINE: &str = r"If true: throw to monkey (\d+)";
 const IF_FALSE_LINE: &str = r"If false: throw to monkey (\d+)";
 
-fn create_monkeys(f: File) -> Vec<Monkey> {
+fn create_monkeys(reader: impl BufRead) -> Result<Vec<Monkey>, ParseError> {
     let mut monkeys: Vec<Monkey> = Vec::new();
-    let mut buffer: Vec<String> = Vec::with_capacity(5);
-
-    for line_ in BufReader::new(f).lines() {
-        if let Ok(line) = line_ {
-            if line.len() == 0 {
-                monkeys.push(create_monkey(&buffer));
-                buffer.clear();
-            } else if line.starts_with("Monkey") {
-                continue;
-            } else {
-                buffer.push(line);
+    for paragraph in parse::paragraphs(reader) {
+        monkeys.push(create_monkey(paragraph?)?);
+    }
+    if monkeys.is_empty() {
+        return Err(ParseError::at_line(1, "expected at least two monkeys"));
+    }
+    let mut held: usize = 0;
+    for monkey in &monkeys {
+        held += monkey.items.len();
+        if held > MAX_ITEMS {
+            return Err(monkey.lines[1].error_at_end(format!(
+                "the monkeys hold more than {} items between them",
+                MAX_ITEMS
+            )));
+        }
+    }
+
+    let mut errors: Vec<ParseError> = Vec::new();
+    for (idx, monkey) in monkeys.iter().enumerate() {
+        for (target, line) in [
+            (monkey.if_true, &monkey.lines[4]),
+            (monkey.if_false, &monkey.lines[5]),
+        ] {
+            let field = line.text.trim_end().rsplit(' ').next().unwrap_or_default();
+            if target == idx {
+                errors.push(line.error_at(field, format!("monkey {} can't throw to itself", idx)));
+            } else if target >= monkeys.len() {
+                errors.push(line.error_at(field, format!("there is no monkey {}", target)));
             }
         }
     }
-    if buffer.len() == 5 {
-        monkeys.push(create_monkey(&buffer));
+    if errors.is_empty() {
+        Ok(monkeys)
+    } else {
+        Err(ParseError::combine(errors))
     }
-    monkeys
 }
 
-fn create_monkey(buffer: &Vec<String>) -> Monkey {
-    let operation_line: Regex = Regex::new(&OPERATION_LINE).unwrap();
-    let quotient_line: Regex = Regex::new(&QUOTIENT_LINE).unwrap();
-    let if_true_line: Regex = Regex::new(&IF_TRUE_LINE).unwrap();
-    let if_false_line: Regex = Regex::new(&IF_FALSE_LINE).unwrap();
+fn capture<'a>(re: &Regex, line: &'a Line, expected: &str) -> Result<Vec<&'a str>, ParseError> {
+    let caps = re
+        .captures(line.text.trim())
+        .ok_or_else(|| line.error(1, format!("expected `{}`", expected)))?;
+    Ok(caps.iter().skip(1).flatten().map(|m| m.as_str()).collect())
+}
+
+fn create_monkey(buffer: Vec<Line>) -> Result<Monkey, ParseError> {
+    let operation_line: Regex = Regex::new(OPERATION_LINE).unwrap();
+    let quotient_line: Regex = Regex::new(QUOTIENT_LINE).unwrap();
+    let if_true_line: Regex = Regex::new(IF_TRUE_LINE).unwrap();
+    let if_false_line: Regex = Regex::new(IF_FALSE_LINE).unwrap();
+
+    let header = &buffer[0];
+    if !header.text.starts_with("Monkey") {
+        return Err(header.error(1, "expected `Monkey N:`"));
+    }
+    if buffer.len() != 6 {
+        let last = &buffer[buffer.len() - 1];
+        return Err(last.error_at_end(format!(
+            "expected 5 lines describing the monkey, found {}",
+            buffer.len() - 1
+        )));
+    }
 
-    let parts: Vec<_> = buffer[0].split(": ").collect();
-    let items: VecDeque<Item> = parts[1]
+    let starting = &buffer[1];
+    let (_, listed) = starting.split_once(&starting.text, ": ")?;
+    let items: VecDeque<Item> = listed
         .split(", ")
-        .map(|x| x.parse::<Item>().unwrap())
-        .collect();
-    let operation_parts = operation_line.captures(&buffer[1]).unwrap();
-    let operation = Operation::from_str(&operation_parts[1]);
-    let operand: Item = match &operation_parts[2] {
+        .map(|x| starting.parse::<Item>(x))
+        .collect::<Result<_, _>>()?;
+    let operation_parts = capture(&operation_line, &buffer[2], "Operation: new = old OP N")?;
+    let operation = buffer[2].convert(operation_parts[0], Operation::from_str, "+ or *")?;
+    let operand: Item = match operation_parts[1] {
         "old" => 0,
-        s => s.parse::<Item>().unwrap(),
+        s => buffer[2].parse::<Item>(s)?,
     };
-    let quotient: Item = quotient_line.captures(&buffer[2]).unwrap()[1]
-        .parse()
-        .unwrap();
-    let if_true: usize = if_true_line.captures(&buffer[3]).unwrap()[1]
-        .parse()
-        .unwrap();
-    let if_false: usize = if_false_line.captures(&buffer[4]).unwrap()[1]
-        .parse()
-        .unwrap();
-    Monkey {
+    let divisor = capture(&quotient_line, &buffer[3], "Test: divisible by N")?[0];
+    let quotient: Item = buffer[3].parse(divisor)?;
+    if quotient == 0 {
+        return Err(buffer[3].error_at(divisor, "can't test for divisibility by 0"));
+    }
+    let if_true: usize =
+        buffer[4].parse(capture(&if_true_line, &buffer[4], "If true: throw to monkey N")?[0])?;
+    let if_false: usize =
+        buffer[5].parse(capture(&if_false_line, &buffer[5], "If false: throw to monkey N")?[0])?;
+    Ok(Monkey {
         items,
         operation,
         operand,
         quotient,
         if_true,
         if_false,
-    }
+        lines: buffer,
+    })
 }
 
 #[derive(Debug)]
@@ -121,18 +186,18 @@ enum Operation {
 }
 
 impl Operation {
-    fn from_str(s: &str) -> Self {
+    fn from_str(s: &str) -> Option<Self> {
         match s {
-            "+" => Operation::Add,
-            "*" => Operation::Multiply,
-            _ => panic!("Invalid operation received"),
+            "+" => Some(Operation::Add),
+            "*" => Some(Operation::Multiply),
+            _ => None,
         }
     }
 
-    fn perform(&self, a: &Item, b: &Item) -> Item {
+    fn perform(&self, a: &Item, b: &Item) -> Option<Item> {
         match self {
-            Operation::Add => a + b,
-            Operation::Multiply => a * b,
+            Operation::Add => a.checked_add(*b),
+            Operation::Multiply => a.checked_mul(*b),
         }
     }
 }
@@ -145,23 +210,106 @@ struct Monkey {
     quotient: Item,
     if_true: usize,
     if_false: usize,
+    /// The lines describing the monkey, for pointing errors at
+    lines: Vec<Line>,
 }
 
 impl Monkey {
-    fn inspect_item(&mut self, destress: &StressManager) -> (usize, Item) {
+    fn inspect_item(&mut self, destress: &StressManager) -> Result<(usize, Item), ParseError> {
         let mut item = self.items.pop_front().expect("Why is there no item?");
-        if self.operand == 0 {
-            item = self.operation.perform(&item, &item);
+        let operand = if self.operand == 0 {
+            item
         } else {
-            item = self.operation.perform(&item, &self.operand);
-        }
+            self.operand
+        };
+        item = self.operation.perform(&item, &operand).ok_or_else(|| {
+            self.lines[2].error_at_end(format!("a worry level grows past {}", Item::MAX))
+        })?;
 
         item = destress.perform(item);
         // let new_val = item.clone();
-        if item % &self.quotient == 0 {
-            (self.if_true, item)
+        if item.is_multiple_of(self.quotient) {
+            Ok((self.if_true, item))
         } else {
-            (self.if_false, item)
+            Ok((self.if_false, item))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn example() {
+        assert_eq!(solve(&mut EXAMPLE.as_bytes(), 1).unwrap(), "10605");
+        assert_eq!(solve(&mut EXAMPLE.as_bytes(), 2).unwrap(), "2713310158");
+    }
+
+    #[test]
+    fn rejects_impossible_throws() {
+        let input = EXAMPLE
+            .replacen("throw to monkey 3", "throw to monkey 0", 1)
+            .replacen("throw to monkey 0", "throw to monkey 7", 2);
+        let errors: Vec<String> = match create_monkeys(input.as_bytes()) {
+            Err(ParseError::Many(errors)) => errors.iter().map(|e| e.to_string()).collect(),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            errors,
+            vec![
+                "line 6, column 31: there is no monkey 7",
+                "line 13, column 31: there is no monkey 7",
+            ]
+        );
+        let input = EXAMPLE.replacen("throw to monkey 3", "throw to monkey 0", 1);
+        match create_monkeys(input.as_bytes()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "line 6, column 31: monkey 0 can't throw to itself"
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        let input = EXAMPLE.replacen("divisible by 23", "divisible by 0", 1);
+        assert!(create_monkeys(input.as_bytes()).is_err());
+        assert!(create_monkeys("".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn reports_worry_overflow() {
+        let input = EXAMPLE.replace("old * 19", "old * 18446744073709551615");
+        match solve(&mut input.as_bytes(), 1) {
+            Err(e) => assert!(e.to_string().starts_with("line 3, "), "{}", e),
+            other => panic!("unexpected {:?}", other),
         }
     }
 }
@@ -1,12 +1,16 @@
+use crate::animate;
+use crate::parse::{self, Line, ParseError};
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt;
+use std::io::BufRead;
 
 type Map = Vec<Vec<char>>;
 type Coord = (usize, usize);
 type InvalidMoves = HashMap<Coord, Vec<Instruction>>;
 
-#[derive(Copy, Clone, Debug)]
+const NO_ROUTE: &str = "No route to the best signal!";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum Instruction {
     Up,
     Down,
@@ -52,6 +56,7 @@ impl Instruction {
         }
     }
 
+    #[allow(dead_code)]
     fn opposite(&self) -> Self {
         match &self {
             Instruction::Up => Instruction::Down,
@@ -62,6 +67,7 @@ impl Instruction {
     }
 }
 
+#[allow(dead_code)]
 enum RouteStatus {
     Deadend(Coord),
     Arrived(Vec<Coord>),
@@ -70,6 +76,7 @@ enum RouteStatus {
 
 #[derive(Debug)]
 struct Brain<'a> {
+    // squares already reached by a shorter route lead nowhere new
     deadends: HashSet<Coord>,
     invalids: InvalidMoves,
     start: Coord,
@@ -81,7 +88,7 @@ impl<'a> Brain<'a> {
     fn new(map: &'a Map) -> Self {
         let (start, end) = find_start_and_end(map);
         let invalids = check_for_invalid_moves(map);
-        println!("Start = {:?} | End = {:?}", start, end);
+        log::debug!("Start = {:?} | End = {:?}", start, end);
         Brain {
             deadends: HashSet::new(),
             invalids,
@@ -90,24 +97,163 @@ impl<'a> Brain<'a> {
             map,
         }
     }
+
+    fn moves_from(&self, point: Coord) -> impl Iterator<Item = Coord> + '_ {
+        let invalid = &self.invalids[&point];
+        DIRECTIONS
+            .iter()
+            .filter(move |instr| !invalid.contains(instr))
+            .filter_map(move |instr| instr.from(self.map, point))
+    }
+
+    /// Breadth-first search outwards from every one of `starts`, returning the
+    /// number of steps needed to reach the end
+    fn search(&mut self, starts: Vec<Coord>) -> Option<usize> {
+        let mut frontier = starts;
+        let mut steps: usize = 0;
+        self.deadends.extend(frontier.iter().copied());
+        while !frontier.is_empty() {
+            if animate::enabled() {
+                let view = SearchView {
+                    brain: self,
+                    frontier: &frontier,
+                };
+                animate::frame(&format!("Step {}", steps), &view);
+            }
+            if frontier.contains(&self.end) {
+                return Some(steps);
+            }
+            let mut next: Vec<Coord> = Vec::with_capacity(frontier.len() * 2);
+            for &point in &frontier {
+                let moves: Vec<Coord> = self.moves_from(point).collect();
+                for to in moves {
+                    if self.deadends.insert(to) {
+                        next.push(to);
+                    }
+                }
+            }
+            frontier = next;
+            steps += 1;
+        }
+        None
+    }
 }
 
-pub fn day_main(filename: &str, _parts: u8) {
-    let f = File::open(filename).expect("Unable to read file");
-    let map: Map = read_map(f);
-    let brain = Brain::new(&map);
-    // println!("The brain is live! {:?}", brain);
+/// The heightmap with the squares explored so far and the current search frontier
+struct SearchView<'a> {
+    brain: &'a Brain<'a>,
+    frontier: &'a [Coord],
 }
 
-fn read_map(f: File) -> Map {
+impl fmt::Display for SearchView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row_idx, row) in self.brain.map.iter().enumerate() {
+            for (col_idx, &item) in row.iter().enumerate() {
+                let point = (row_idx, col_idx);
+                if self.frontier.contains(&point) {
+                    write!(f, "@")?;
+                } else if self.brain.deadends.contains(&point) {
+                    write!(f, ".")?;
+                } else {
+                    write!(f, "{}", item)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The fewest steps to the best signal, unless it can't be reached at all
+fn fewest_steps(input: &mut dyn BufRead, part: u8) -> Result<Option<usize>, ParseError> {
+    let map: Map = read_map(input)?;
+    let mut brain = Brain::new(&map);
+    log::trace!("The brain is live! {:?}", brain);
+    let starts = match part {
+        1 => vec![brain.start],
+        2 => lowest_points(&map),
+        _ => panic!("Not implemented"),
+    };
+    Ok(brain.search(starts))
+}
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    Ok(match fewest_steps(input, part)? {
+        Some(steps) => steps.to_string(),
+        None => NO_ROUTE.to_owned(),
+    })
+}
+
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    match fewest_steps(&mut parse::open(filename)?, part)? {
+        Some(steps) => println!("Fewest steps to the best signal: {}", steps),
+        None => println!("{}", NO_ROUTE),
+    }
+    Ok(())
+}
+
+fn elevation(c: char) -> u8 {
+    match c {
+        'S' => b'a',
+        'E' => b'z',
+        _ => c as u8,
+    }
+}
+
+fn lowest_points(map: &Map) -> Vec<Coord> {
+    let mut lowest: Vec<Coord> = Vec::new();
+    for (row_idx, row) in map.iter().enumerate() {
+        for (col_idx, &item) in row.iter().enumerate() {
+            if elevation(item) == b'a' {
+                lowest.push((row_idx, col_idx));
+            }
+        }
+    }
+    lowest
+}
+
+/// A rectangular map of `a` to `z`, with exactly one `S` and one `E`
+fn read_map(reader: impl BufRead) -> Result<Map, ParseError> {
     let mut map: Map = Vec::new();
-    for line_ in BufReader::new(f).lines() {
-        if let Ok(line) = line_ {
-            let chars: Vec<char> = line.chars().collect();
-            map.push(chars);
+    let mut last: Option<Line> = None;
+    let mut marked = [('S', false), ('E', false)];
+    for line in parse::lines(reader) {
+        let line = last.insert(line?);
+        let chars: Vec<char> = line.text.chars().collect();
+        for (idx, &c) in chars.iter().enumerate() {
+            if let Some((_, seen)) = marked.iter_mut().find(|(mark, _)| *mark == c) {
+                if *seen {
+                    return Err(line.error(idx + 1, format!("more than one `{}` on the map", c)));
+                }
+                *seen = true;
+            } else if !c.is_ascii_lowercase() {
+                return Err(line.error(idx + 1, format!("expected an elevation, found `{}`", c)));
+            }
+        }
+        if let Some(first) = map.first() {
+            if chars.len() != first.len() {
+                return Err(line.error(
+                    chars.len().min(first.len()) + 1,
+                    format!(
+                        "expected {} squares in the row, found {}",
+                        first.len(),
+                        chars.len()
+                    ),
+                ));
+            }
+        }
+        map.push(chars);
+    }
+    for (mark, seen) in marked {
+        if !seen {
+            let message = format!("no `{}` on the map", mark);
+            return Err(match &last {
+                Some(line) => line.error_at_end(message),
+                None => ParseError::at_line(1, message),
+            });
         }
     }
-    map
+    Ok(map)
 }
 
 fn find_start_and_end(map: &Map) -> (Coord, Coord) {
@@ -139,7 +285,7 @@ fn check_instruction(
     match instr.from(map, (row_idx, col_idx)) {
         None => bad_to_start.push(instr),
         Some((next_row, next_col)) => {
-            if (map[next_row][next_col] as u8) > (map[row_idx][col_idx] as u8) + 1 {
+            if elevation(map[next_row][next_col]) > elevation(map[row_idx][col_idx]) + 1 {
                 bad_to_start.push(instr);
             }
         }
@@ -149,7 +295,7 @@ fn check_instruction(
 fn check_for_invalid_moves(map: &Map) -> InvalidMoves {
     let mut invalids: InvalidMoves = HashMap::with_capacity(map.len() * map[0].len());
     for (row_idx, row) in map.iter().enumerate() {
-        for (col_idx, &item) in row.iter().enumerate() {
+        for (col_idx, _) in row.iter().enumerate() {
             let mut bad_to_start = Vec::new();
             for instr in DIRECTIONS {
                 check_instruction(instr, map, row_idx, col_idx, &mut bad_to_start);
@@ -157,6 +303,84 @@ fn check_for_invalid_moves(map: &Map) -> InvalidMoves {
             invalids.insert((row_idx, col_idx), bad_to_start);
         }
     }
-    println!("Invalids:\n{:?}", invalids);
+    log::debug!("Invalids:\n{:?}", invalids);
     invalids
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n";
+
+    #[test]
+    fn shortest_from_start() {
+        let map = read_map(EXAMPLE.as_bytes()).unwrap();
+        let mut brain = Brain::new(&map);
+        let start = brain.start;
+        assert_eq!(brain.search(vec![start]), Some(31));
+    }
+
+    #[test]
+    fn solves_the_example() {
+        assert_eq!(solve(&mut EXAMPLE.as_bytes(), 1).unwrap(), "31");
+        assert_eq!(solve(&mut EXAMPLE.as_bytes(), 2).unwrap(), "29");
+    }
+
+    #[test]
+    fn start_and_end_are_lowest_and_highest() {
+        let climb = |map: &str| solve(&mut map.as_bytes(), 1).unwrap();
+        assert_eq!(climb("SbcdefghijklmnopqrstuvwxyE\n"), "25");
+        assert_eq!(climb("SbcdefghijklmnopqrstuvwxE\n"), NO_ROUTE);
+        assert_eq!(climb("ScE\n"), NO_ROUTE);
+    }
+
+    #[test]
+    fn draws_the_search_frontier() {
+        let map = read_map(EXAMPLE.as_bytes()).unwrap();
+        let mut brain = Brain::new(&map);
+        brain.deadends.extend([(0, 0), (0, 1), (1, 0)]);
+        let view = SearchView {
+            brain: &brain,
+            frontier: &[(0, 1), (1, 0)],
+        };
+        assert_eq!(
+            view.to_string(),
+            ".@bqponm\n@bcryxxl\naccszExk\nacctuvwj\nabdefghi\n"
+        );
+    }
+
+    #[test]
+    fn shortest_from_any_lowest() {
+        let map = read_map(EXAMPLE.as_bytes()).unwrap();
+        let mut brain = Brain::new(&map);
+        assert_eq!(brain.search(lowest_points(&map)), Some(29));
+    }
+
+    #[test]
+    fn rejects_broken_maps() {
+        let error = |input: &str| read_map(input.as_bytes()).unwrap_err().to_string();
+        assert_eq!(
+            error("SabE\nabc\n"),
+            "line 2, column 4: expected 4 squares in the row, found 3"
+        );
+        assert_eq!(
+            error("Sab\naEA\n"),
+            "line 2, column 3: expected an elevation, found `A`"
+        );
+        assert_eq!(
+            error("SaS\n"),
+            "line 1, column 3: more than one `S` on the map"
+        );
+        assert_eq!(error("Sab\n"), "line 1, column 4: no `E` on the map");
+        assert_eq!(error(""), "line 1, column 1: no `S` on the map");
+    }
+
+    #[test]
+    fn no_route_is_an_answer() {
+        assert_eq!(
+            solve(&mut "Sz\nzE\n".as_bytes(), 1).unwrap(),
+            "No route to the best signal!"
+        );
+    }
+}
@@ -1,157 +1,1269 @@
-use std::cmp::Ordering;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::parse::{self, FromLine, Line, ParseError};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Throw {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3,
+/// One kind of throw in a game, along with the letters standing for it in a strategy guide
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Kind {
+    name: String,
+    theirs: String,
+    ours: String,
+    points: i32,
+}
+
+/// A cyclic game with an odd number of throws, listed in cycle order so that
+/// each throw beats the (n-1)/2 throws before it, wrapping around
+#[derive(Clone, Debug)]
+pub struct Game {
+    kinds: Vec<Kind>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// A throw in some game, by its position in the game's cycle
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+struct Throw(usize);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Outcome {
     Win = 6,
     Lose = 0,
     Draw = 3,
 }
 
-trait Strategy {
-    fn from_str(c: &str) -> Self;
+trait Strategy: Sized {
+    fn parse(game: &Game, c: &str) -> Option<Self>;
 
-    fn points_against(&self, against: &Throw) -> i32;
+    #[cfg(test)]
+    fn from_str(game: &Game, c: &str) -> Self {
+        Self::parse(game, c).expect("Invalid strategy code")
+    }
+
+    fn points_against(&self, game: &Game, against: &Throw) -> i32;
 }
 
-impl PartialOrd for Throw {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Kind {
+    fn new(name: &str, theirs: &str, ours: &str, points: i32) -> Kind {
+        Kind {
+            name: name.to_owned(),
+            theirs: theirs.to_owned(),
+            ours: ours.to_owned(),
+            points,
+        }
     }
 }
 
-impl Ord for Throw {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self == other {
-            Ordering::Equal
-        } else {
-            match (self, other) {
-                (Throw::Rock, Throw::Scissors)
-                | (Throw::Scissors, Throw::Paper)
-                | (Throw::Paper, Throw::Rock) => Ordering::Greater,
-                _ => Ordering::Less,
-            }
+/// The most a throw may be worth, well clear of overflowing when a round is scored
+const MAX_POINTS: i32 = 1000;
+
+impl FromLine for Kind {
+    /// `name theirs ours points`, e.g. `Rock A X 1`
+    fn from_line(line: &Line) -> Result<Kind, ParseError> {
+        let mut fields = line.text.split_ascii_whitespace();
+        let name = line.next_field(&mut fields, "throw name")?;
+        let theirs = line.next_field(&mut fields, "opponent's code")?;
+        let ours = line.next_field(&mut fields, "our code")?;
+        let points = line.next_field(&mut fields, "point value")?;
+        if let Some(extra) = fields.next() {
+            return Err(line.error_at(extra, format!("unexpected `{}`", extra)));
+        }
+        let value: i32 = line.parse(points)?;
+        if !(0..=MAX_POINTS).contains(&value) {
+            return Err(line.error_at(
+                points,
+                format!("a throw is worth 0 to {} points, not {}", MAX_POINTS, value),
+            ));
         }
+        Ok(Kind::new(name, theirs, ours, value))
     }
 }
 
-impl Throw {
-    fn against(&self, other: &Self) -> Outcome {
-        match self.cmp(other) {
-            Ordering::Less => Outcome::Lose,
-            Ordering::Equal => Outcome::Draw,
-            Ordering::Greater => Outcome::Win,
+impl Game {
+    pub fn rock_paper_scissors() -> Game {
+        Game {
+            kinds: vec![
+                Kind::new("Rock", "A", "X", 1),
+                Kind::new("Paper", "B", "Y", 2),
+                Kind::new("Scissors", "C", "Z", 3),
+            ],
         }
     }
 
-    fn to_achieve(against: &Throw, outcome: &Outcome) -> Throw {
-        match outcome {
-            Outcome::Draw => against.clone(),
-            Outcome::Win => match against {
-                Throw::Rock => Throw::Paper,
-                Throw::Paper => Throw::Scissors,
-                Throw::Scissors => Throw::Rock,
-            },
-            Outcome::Lose => match against {
-                Throw::Rock => Throw::Scissors,
-                Throw::Paper => Throw::Rock,
-                Throw::Scissors => Throw::Paper,
-            },
+    pub fn rock_paper_scissors_lizard_spock() -> Game {
+        Game {
+            kinds: vec![
+                Kind::new("Rock", "A", "V", 1),
+                Kind::new("Spock", "B", "W", 2),
+                Kind::new("Paper", "C", "X", 3),
+                Kind::new("Lizard", "D", "Y", 4),
+                Kind::new("Scissors", "E", "Z", 5),
+            ],
+        }
+    }
+
+    /// Read a game with one `name theirs ours points` line per throw, in cycle order.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn read(reader: impl BufRead) -> Result<Game, ParseError> {
+        let mut lines: Vec<Line> = Vec::new();
+        for line in parse::lines(reader) {
+            let line = line?;
+            if !line.is_blank() && !line.text.trim_start().starts_with('#') {
+                lines.push(line);
+            }
+        }
+        let kinds = parse::collect_all(lines.iter().map(Kind::from_line))?;
+
+        let mut errors: Vec<ParseError> = Vec::new();
+        for (i, (kind, line)) in kinds.iter().zip(lines.iter()).enumerate() {
+            for earlier in &kinds[..i] {
+                for code in [&kind.theirs, &kind.ours] {
+                    if [&earlier.theirs, &earlier.ours].contains(&code) {
+                        let at = line.text.find(code.as_str()).map_or(1, |at| at + 1);
+                        errors.push(line.error(at, format!("code `{}` is already used", code)));
+                    }
+                }
+            }
+        }
+        if kinds.len() < 3 || kinds.len() % 2 == 0 {
+            let message = format!(
+                "a game needs an odd number of throws, found {}",
+                kinds.len()
+            );
+            match lines.last() {
+                Some(line) => errors.push(line.error_at_end(message)),
+                None => errors.push(ParseError::at_line(1, message)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(Game { kinds })
+        } else {
+            Err(ParseError::combine(errors))
+        }
+    }
+
+    /// `rps`, `rpsls` or the path of a game definition
+    pub fn load(spec: &str) -> Result<Game, ParseError> {
+        match spec {
+            "rps" => Ok(Game::rock_paper_scissors()),
+            "rpsls" => Ok(Game::rock_paper_scissors_lizard_spock()),
+            path => Game::read(parse::open(path)?).map_err(|e| e.in_file(path)),
         }
     }
+
+    fn size(&self) -> usize {
+        self.kinds.len()
+    }
+
+    fn throws(&self) -> impl Iterator<Item = Throw> {
+        (0..self.size()).map(Throw)
+    }
+
+    fn name(&self, throw: Throw) -> &str {
+        &self.kinds[throw.0].name
+    }
+
+    fn points(&self, throw: Throw) -> i32 {
+        self.kinds[throw.0].points
+    }
+
+    /// Whether `mine` is among the (n-1)/2 throws after `theirs` in the cycle
+    fn beats(&self, mine: Throw, theirs: Throw) -> bool {
+        let n = self.size();
+        let distance = (mine.0 + n - theirs.0) % n;
+        distance >= 1 && distance <= (n - 1) / 2
+    }
+
+    fn outcome(&self, mine: Throw, theirs: Throw) -> Outcome {
+        if mine == theirs {
+            Outcome::Draw
+        } else if self.beats(mine, theirs) {
+            Outcome::Win
+        } else {
+            Outcome::Lose
+        }
+    }
+
+    /// The best-scoring throw with the given outcome against `against`
+    fn to_achieve(&self, against: Throw, outcome: Outcome) -> Throw {
+        self.throws()
+            .filter(|&t| self.outcome(t, against) == outcome)
+            .max_by_key(|&t| (self.points(t), std::cmp::Reverse(t.0)))
+            .expect("Every outcome is achievable in a cyclic game")
+    }
+
+    fn their_codes(&self) -> String {
+        codes(self.kinds.iter().map(|k| k.theirs.as_str()))
+    }
+
+    fn our_codes(&self) -> String {
+        codes(self.kinds.iter().map(|k| k.ours.as_str()))
+    }
+}
+
+/// `A, B or C`
+fn codes<'a>(codes: impl Iterator<Item = &'a str>) -> String {
+    let codes: Vec<&str> = codes.collect();
+    match codes.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+impl Throw {
+    fn against(&self, game: &Game, other: &Self) -> Outcome {
+        game.outcome(*self, *other)
+    }
+
+    fn to_achieve(game: &Game, against: &Throw, outcome: &Outcome) -> Throw {
+        game.to_achieve(*against, *outcome)
+    }
+
+    /// What the opponent threw, from their column of the guide
+    fn theirs(game: &Game, c: &str) -> Option<Throw> {
+        game.kinds.iter().position(|k| k.theirs == c).map(Throw)
+    }
 }
 
 impl Strategy for Throw {
-    fn from_str(c: &str) -> Throw {
-        match c {
-            "A" | "X" => Throw::Rock,
-            "B" | "Y" => Throw::Paper,
-            "C" | "Z" => Throw::Scissors,
-            _ => panic!(),
-        }
+    fn parse(game: &Game, c: &str) -> Option<Throw> {
+        Throw::theirs(game, c).or_else(|| game.kinds.iter().position(|k| k.ours == c).map(Throw))
     }
 
-    fn points_against(&self, other: &Self) -> i32 {
-        let outcome = self.against(other);
-        (*self as i32) + (outcome as i32)
+    fn points_against(&self, game: &Game, other: &Self) -> i32 {
+        let outcome = self.against(game, other);
+        game.points(*self) + (outcome as i32)
     }
 }
 
 impl Strategy for Outcome {
-    fn from_str(c: &str) -> Outcome {
+    fn parse(_game: &Game, c: &str) -> Option<Outcome> {
         match c {
-            "X" => Outcome::Lose,
-            "Y" => Outcome::Draw,
-            "Z" => Outcome::Win,
-            _ => panic!(),
+            "X" => Some(Outcome::Lose),
+            "Y" => Some(Outcome::Draw),
+            "Z" => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+
+    fn points_against(&self, game: &Game, against: &Throw) -> i32 {
+        let mine = Throw::to_achieve(game, against, self);
+        mine.points_against(game, against)
+    }
+}
+
+/// Our throw, decoding the hint as a throw for part 1 or an outcome for part 2
+fn strategy(game: &Game, part: u8, hint: &str, theirs: &Throw) -> Option<Throw> {
+    match part {
+        1 => Throw::parse(game, hint),
+        2 => Outcome::parse(game, hint).map(|o| Throw::to_achieve(game, theirs, &o)),
+        _ => panic!("unimplemented part"),
+    }
+}
+
+/// A round of the guide as we end up playing it
+struct Play {
+    line: usize,
+    theirs: Throw,
+    mine: Throw,
+}
+
+impl Play {
+    fn outcome(&self, game: &Game) -> Outcome {
+        self.mine.against(game, &self.theirs)
+    }
+
+    fn points(&self, game: &Game) -> i32 {
+        self.mine.points_against(game, &self.theirs)
+    }
+}
+
+fn plays(game: &Game, input: &mut dyn BufRead, part: u8) -> Result<Vec<Play>, ParseError> {
+    let expected = match part {
+        1 => game.our_codes(),
+        _ => "X, Y or Z".to_owned(),
+    };
+    parse::lines(input)
+        .map(|line| {
+            let line = line?;
+            let (first, second) = line.split_once(&line.text, " ")?;
+            let theirs = line.convert(first, |c| Throw::theirs(game, c), &game.their_codes())?;
+            let mine = line.convert(
+                second,
+                |hint| strategy(game, part, hint, &theirs),
+                &expected,
+            )?;
+            Ok(Play {
+                line: line.number,
+                theirs,
+                mine,
+            })
+        })
+        .collect()
+}
+
+fn score(game: &Game, input: &mut dyn BufRead, part: u8) -> Result<i32, ParseError> {
+    Ok(plays(game, input, part)?
+        .iter()
+        .map(|play| play.points(game))
+        .sum())
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Csv,
+}
+
+/// Wins, draws and losses for each of our throws
+fn tally(game: &Game, plays: &[Play]) -> Vec<[usize; 3]> {
+    let mut tally = vec![[0; 3]; game.size()];
+    for play in plays {
+        let column = match play.outcome(game) {
+            Outcome::Win => 0,
+            Outcome::Draw => 1,
+            Outcome::Lose => 2,
+        };
+        tally[play.mine.0][column] += 1;
+    }
+    tally
+}
+
+/// Every round with its points and the running total, then a summary per throw.
+/// Each CSV row starts with its record type, `round` or `tally`, so the two tables
+/// can be told apart.
+fn report(
+    game: &Game,
+    plays: &[Play],
+    format: ReportFormat,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let width = game.kinds.iter().map(|k| k.name.len()).max().unwrap_or(0);
+    match format {
+        ReportFormat::Text => writeln!(
+            out,
+            "{:>5}  {:width$}  {:width$}  {:7}  {:>5}  {:>7}  {:>6}",
+            "Line", "Theirs", "Ours", "Outcome", "Throw", "Outcome", "Total"
+        )?,
+        ReportFormat::Csv => writeln!(
+            out,
+            "record,line,theirs,ours,outcome,throw_points,outcome_points,total"
+        )?,
+    }
+    let mut total = 0;
+    for play in plays {
+        let outcome = play.outcome(game);
+        total += play.points(game);
+        let (theirs, mine) = (game.name(play.theirs), game.name(play.mine));
+        let (throw_points, outcome_points) = (game.points(play.mine), outcome as i32);
+        match format {
+            ReportFormat::Text => writeln!(
+                out,
+                "{:>5}  {:width$}  {:width$}  {:7}  {:>5}  {:>7}  {:>6}",
+                play.line,
+                theirs,
+                mine,
+                format!("{:?}", outcome),
+                throw_points,
+                outcome_points,
+                total
+            )?,
+            ReportFormat::Csv => writeln!(
+                out,
+                "round,{},{},{},{:?},{},{},{}",
+                play.line, theirs, mine, outcome, throw_points, outcome_points, total
+            )?,
+        }
+    }
+
+    match format {
+        ReportFormat::Text => writeln!(
+            out,
+            "\n{:width$}  {:>5}  {:>5}  {:>6}",
+            "Throw", "Wins", "Draws", "Losses"
+        )?,
+        ReportFormat::Csv => writeln!(out, "record,throw,wins,draws,losses")?,
+    }
+    for (throw, [wins, draws, losses]) in game.throws().zip(tally(game, plays)) {
+        match format {
+            ReportFormat::Text => writeln!(
+                out,
+                "{:width$}  {:>5}  {:>5}  {:>6}",
+                game.name(throw),
+                wins,
+                draws,
+                losses
+            )?,
+            ReportFormat::Csv => writeln!(
+                out,
+                "tally,{},{},{},{}",
+                game.name(throw),
+                wins,
+                draws,
+                losses
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// One line of a strategy guide: what they threw, and the hint for our response
+struct Round {
+    theirs: Throw,
+    hint: String,
+}
+
+/// The guide's rounds, with hints taken from our codes but not yet given a meaning
+fn read_guide(game: &Game, input: &mut dyn BufRead) -> Result<Vec<Round>, ParseError> {
+    let expected = game.our_codes();
+    parse::collect_all(parse::lines(input).map(|line| {
+        let line = line?;
+        let (first, second) = line.split_once(&line.text, " ")?;
+        let theirs = line.convert(first, |c| Throw::theirs(game, c), &game.their_codes())?;
+        let hint = line.convert(
+            second,
+            |c| game.kinds.iter().any(|k| k.ours == c).then(|| c.to_owned()),
+            &expected,
+        )?;
+        Ok(Round { theirs, hint })
+    }))
+}
+
+/// Which decodings to report once every one has been scored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pick {
+    Max,
+    Min,
+    Target(i32),
+}
+
+impl FromStr for Pick {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Pick, String> {
+        match s {
+            "max" => Ok(Pick::Max),
+            "min" => Ok(Pick::Min),
+            n => n
+                .parse()
+                .map(Pick::Target)
+                .map_err(|_| format!("expected `max`, `min` or a score, found `{}`", n)),
+        }
+    }
+}
+
+impl fmt::Display for Pick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pick::Max => write!(f, "the highest score"),
+            Pick::Min => write!(f, "the lowest score"),
+            Pick::Target(points) => write!(f, "a score of {}", points),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Meaning {
+    Throw(Throw),
+    Outcome(Outcome),
+}
+
+/// One possible reading of the codes in our column of the guide
+#[derive(Debug)]
+struct Cipher {
+    codes: Vec<String>,
+    meanings: Vec<Meaning>,
+}
+
+impl Cipher {
+    fn decode(&self, code: &str) -> Option<Meaning> {
+        let at = self.codes.iter().position(|c| c == code)?;
+        Some(self.meanings[at])
+    }
+
+    /// The guide's total under this reading, if every hint in it has a meaning
+    fn score(&self, game: &Game, rounds: &[Round]) -> Option<i32> {
+        rounds
+            .iter()
+            .map(|round| match self.decode(&round.hint)? {
+                Meaning::Throw(t) => Some(t.points_against(game, &round.theirs)),
+                Meaning::Outcome(o) => Some(o.points_against(game, &round.theirs)),
+            })
+            .sum()
+    }
+
+    fn describe(&self, game: &Game) -> String {
+        self.codes
+            .iter()
+            .zip(self.meanings.iter())
+            .map(|(code, meaning)| match meaning {
+                Meaning::Throw(t) => format!("{}={}", code, game.name(*t)),
+                Meaning::Outcome(o) => format!("{}={:?}", code, o),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// Every ordering of `0..n`
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut found: Vec<Vec<usize>> = Vec::new();
+    for shorter in permutations(n - 1) {
+        for at in 0..=shorter.len() {
+            let mut longer = shorter.clone();
+            longer.insert(at, n - 1);
+            found.push(longer);
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Every bijection from our codes to throws, then from X/Y/Z to outcomes
+fn ciphers(game: &Game) -> Vec<Cipher> {
+    let codes: Vec<String> = game.kinds.iter().map(|k| k.ours.clone()).collect();
+    let mut found: Vec<Cipher> = permutations(game.size())
+        .into_iter()
+        .map(|order| Cipher {
+            codes: codes.clone(),
+            meanings: order
+                .into_iter()
+                .map(|i| Meaning::Throw(Throw(i)))
+                .collect(),
+        })
+        .collect();
+    let outcomes = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+    found.extend(permutations(outcomes.len()).into_iter().map(|order| {
+        Cipher {
+            codes: vec!["X".to_owned(), "Y".to_owned(), "Z".to_owned()],
+            meanings: order
+                .into_iter()
+                .map(|i| Meaning::Outcome(outcomes[i]))
+                .collect(),
+        }
+    }));
+    found
+}
+
+/// The decodings matching `pick`, along with their scores
+fn pick_ciphers(scored: &[(Cipher, i32)], pick: Pick) -> Vec<&(Cipher, i32)> {
+    let wanted = match pick {
+        Pick::Max => scored.iter().map(|(_, points)| *points).max(),
+        Pick::Min => scored.iter().map(|(_, points)| *points).min(),
+        Pick::Target(points) => Some(points),
+    };
+    scored
+        .iter()
+        .filter(|(_, points)| Some(*points) == wanted)
+        .collect()
+}
+
+fn decode(game: &Game, filename: &str, pick: Pick) -> Result<(), ParseError> {
+    let rounds = read_guide(game, &mut parse::open(filename)?)?;
+    let mut scored: Vec<(Cipher, i32)> = Vec::new();
+    for cipher in ciphers(game) {
+        match cipher.score(game, &rounds) {
+            Some(points) => {
+                println!("{}: {} points", cipher.describe(game), points);
+                scored.push((cipher, points));
+            }
+            None => log::debug!("{} leaves some hints undecoded", cipher.describe(game)),
+        }
+    }
+    let picked = pick_ciphers(&scored, pick);
+    if picked.is_empty() {
+        println!("No decoding gives {}", pick);
+    }
+    for (cipher, points) in picked {
+        println!(
+            "The elf must have meant {} ({} points)",
+            cipher.describe(game),
+            points
+        );
+    }
+    Ok(())
+}
+
+/// How often the opponent makes each throw, overall and straight after each of their throws
+struct OpponentModel {
+    counts: Vec<usize>,
+    transitions: Vec<Vec<usize>>,
+}
+
+impl OpponentModel {
+    fn observe(game: &Game, throws: &[Throw]) -> OpponentModel {
+        let mut counts = vec![0; game.size()];
+        let mut transitions = vec![vec![0; game.size()]; game.size()];
+        for throw in throws {
+            counts[throw.0] += 1;
+        }
+        for pair in throws.windows(2) {
+            transitions[pair[0].0][pair[1].0] += 1;
+        }
+        OpponentModel {
+            counts,
+            transitions,
+        }
+    }
+
+    fn frequencies(&self) -> Vec<f64> {
+        distribution(&self.counts)
+    }
+
+    /// The distribution of their next throw after `previous`, unless they never followed it up
+    fn after(&self, previous: Throw) -> Option<Vec<f64>> {
+        let seen = &self.transitions[previous.0];
+        (seen.iter().sum::<usize>() > 0).then(|| distribution(seen))
+    }
+}
+
+fn distribution(counts: &[usize]) -> Vec<f64> {
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .map(|&c| c as f64 / total.max(1) as f64)
+        .collect()
+}
+
+/// The throw with the highest expected score against `distribution`, and that score
+fn best_response(game: &Game, distribution: &[f64]) -> (Throw, f64) {
+    game.throws()
+        .map(|mine| {
+            let expected = game
+                .throws()
+                .map(|theirs| distribution[theirs.0] * mine.points_against(game, &theirs) as f64)
+                .sum();
+            (mine, expected)
+        })
+        .fold(
+            None,
+            |best: Option<(Throw, f64)>, (mine, expected)| match best {
+                Some((_, most)) if most >= expected => best,
+                _ => Some((mine, expected)),
+            },
+        )
+        .expect("A game has throws")
+}
+
+/// Our responses to each round under the transition model, with the expected total.
+/// The first round, and any throw they never followed up, fall back on their frequencies.
+fn transition_responses(game: &Game, model: &OpponentModel, throws: &[Throw]) -> (Vec<Throw>, f64) {
+    let fallback = best_response(game, &model.frequencies());
+    let mut previous: Option<Throw> = None;
+    let mut responses: Vec<Throw> = Vec::new();
+    let mut expected = 0.0;
+    for throw in throws {
+        let (mine, points) = previous
+            .and_then(|p| model.after(p))
+            .map_or(fallback, |d| best_response(game, &d));
+        responses.push(mine);
+        expected += points;
+        previous = Some(*throw);
+    }
+    (responses, expected)
+}
+
+fn counter(game: &Game, filename: &str, part: u8) -> Result<(), ParseError> {
+    let rounds = read_guide(game, &mut parse::open(filename)?)?;
+    let throws: Vec<Throw> = rounds.iter().map(|round| round.theirs).collect();
+    let model = OpponentModel::observe(game, &throws);
+    let realised = |responses: &mut dyn Iterator<Item = Throw>| -> i32 {
+        responses
+            .zip(throws.iter())
+            .map(|(mine, theirs)| mine.points_against(game, theirs))
+            .sum()
+    };
+
+    let frequencies = model.frequencies();
+    println!(
+        "Opponent threw {}",
+        game.throws()
+            .map(|t| format!("{} {:.1}%", game.name(t), frequencies[t.0] * 100.0))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    let (always, per_round) = best_response(game, &frequencies);
+    println!(
+        "Against their frequencies: always {}, expecting {:.1} points ({} on this guide)",
+        game.name(always),
+        per_round * throws.len() as f64,
+        realised(&mut std::iter::repeat(always))
+    );
+
+    let (responses, expected) = transition_responses(game, &model, &throws);
+    let replies: Vec<String> = game
+        .throws()
+        .filter_map(|previous| {
+            let (mine, _) = best_response(game, &model.after(previous)?);
+            Some(format!("{} after {}", game.name(mine), game.name(previous)))
+        })
+        .collect();
+    println!(
+        "Against their transitions: {}, expecting {:.1} points ({} on this guide)",
+        replies.join(", "),
+        expected,
+        realised(&mut responses.into_iter())
+    );
+
+    let perfect: i32 = throws
+        .iter()
+        .map(|theirs| Outcome::Win.points_against(game, theirs))
+        .sum();
+    let actual = score(game, &mut parse::open(filename)?, part)?;
+    println!(
+        "The guide scores {} points; winning every round would score {}",
+        actual, perfect
+    );
+    Ok(())
+}
+
+/// A tournament bot, choosing each throw given the match so far as (ours, theirs) pairs
+trait Player {
+    fn name(&self, game: &Game) -> String;
+
+    fn throw(&mut self, game: &Game, history: &[(Throw, Throw)]) -> Throw;
+
+    /// Take note of a round once both sides have thrown
+    fn observe(&mut self, _game: &Game, _ours: Throw, _theirs: Throw) {}
+
+    /// Forget anything carried over from an earlier match
+    fn reset(&mut self) {}
+}
+
+struct Fixed(Throw);
+
+impl Player for Fixed {
+    fn name(&self, game: &Game) -> String {
+        format!("Always {}", game.name(self.0))
+    }
+
+    fn throw(&mut self, _game: &Game, _history: &[(Throw, Throw)]) -> Throw {
+        self.0
+    }
+}
+
+struct Cycling;
+
+impl Player for Cycling {
+    fn name(&self, _game: &Game) -> String {
+        "Cycling".to_owned()
+    }
+
+    fn throw(&mut self, game: &Game, history: &[(Throw, Throw)]) -> Throw {
+        Throw(history.len() % game.size())
+    }
+}
+
+struct CopyLast;
+
+impl Player for CopyLast {
+    fn name(&self, _game: &Game) -> String {
+        "Copy last".to_owned()
+    }
+
+    fn throw(&mut self, _game: &Game, history: &[(Throw, Throw)]) -> Throw {
+        history.last().map_or(Throw(0), |&(_, theirs)| theirs)
+    }
+}
+
+struct BeatLast;
+
+impl Player for BeatLast {
+    fn name(&self, _game: &Game) -> String {
+        "Beat last".to_owned()
+    }
+
+    fn throw(&mut self, game: &Game, history: &[(Throw, Throw)]) -> Throw {
+        history.last().map_or(Throw(0), |(_, theirs)| {
+            Throw::to_achieve(game, theirs, &Outcome::Win)
+        })
+    }
+}
+
+/// Plays the best response to how often the opponent has made each throw so far
+#[derive(Default)]
+struct FrequencyCounter {
+    counts: Vec<usize>,
+}
+
+impl Player for FrequencyCounter {
+    fn name(&self, _game: &Game) -> String {
+        "Frequency counter".to_owned()
+    }
+
+    fn throw(&mut self, game: &Game, _history: &[(Throw, Throw)]) -> Throw {
+        self.counts.resize(game.size(), 0);
+        best_response(game, &distribution(&self.counts)).0
+    }
+
+    fn observe(&mut self, game: &Game, _ours: Throw, theirs: Throw) {
+        self.counts.resize(game.size(), 0);
+        self.counts[theirs.0] += 1;
+    }
+
+    fn reset(&mut self) {
+        self.counts.clear();
+    }
+}
+
+/// Throws uniformly at random from a xorshift generator, so that matches can be repeated
+struct Random {
+    seed: u64,
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        let mut random = Random { seed, state: 0 };
+        random.reset();
+        random
+    }
+}
+
+impl Player for Random {
+    fn name(&self, _game: &Game) -> String {
+        format!("Random (seed {})", self.seed)
+    }
+
+    fn throw(&mut self, game: &Game, _history: &[(Throw, Throw)]) -> Throw {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        Throw((self.state % game.size() as u64) as usize)
+    }
+
+    /// Start over from the seed; xorshift never leaves a zero state, so 0 gets a
+    /// constant of its own rather than sharing another seed's sequence
+    fn reset(&mut self) {
+        self.state = if self.seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            self.seed
+        };
+    }
+}
+
+/// Every bot, to be reset before each match since players may keep state between throws
+fn roster(game: &Game, seed: u64) -> Vec<Box<dyn Player>> {
+    let mut players: Vec<Box<dyn Player>> = game
+        .throws()
+        .map(|t| Box::new(Fixed(t)) as Box<dyn Player>)
+        .collect();
+    players.push(Box::new(Cycling));
+    players.push(Box::new(CopyLast));
+    players.push(Box::new(BeatLast));
+    players.push(Box::<FrequencyCounter>::default());
+    players.push(Box::new(Random::new(seed)));
+    players
+}
+
+/// The most rounds a tournament match may last
+const MAX_ROUNDS: usize = 1_000_000;
+
+/// Each side's total score after `rounds` throws, widened so that no number of
+/// rounds up to `MAX_ROUNDS` can overflow it
+fn play_match(game: &Game, a: &mut dyn Player, b: &mut dyn Player, rounds: usize) -> (i64, i64) {
+    let mut seen_by_a: Vec<(Throw, Throw)> = Vec::new();
+    let mut seen_by_b: Vec<(Throw, Throw)> = Vec::new();
+    let (mut a_points, mut b_points): (i64, i64) = (0, 0);
+    for _ in 0..rounds {
+        let a_throw = a.throw(game, &seen_by_a);
+        let b_throw = b.throw(game, &seen_by_b);
+        a_points += i64::from(a_throw.points_against(game, &b_throw));
+        b_points += i64::from(b_throw.points_against(game, &a_throw));
+        a.observe(game, a_throw, b_throw);
+        b.observe(game, b_throw, a_throw);
+        seen_by_a.push((a_throw, b_throw));
+        seen_by_b.push((b_throw, a_throw));
+    }
+    (a_points, b_points)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Standing {
+    name: String,
+    won: usize,
+    drawn: usize,
+    lost: usize,
+    score: i64,
+}
+
+impl Standing {
+    fn played(&self) -> usize {
+        self.won + self.drawn + self.lost
+    }
+
+    /// Three for a win and one for a draw
+    fn league_points(&self) -> usize {
+        3 * self.won + self.drawn
+    }
+
+    fn record(&mut self, ours: i64, theirs: i64) {
+        self.score += ours;
+        match ours.cmp(&theirs) {
+            std::cmp::Ordering::Greater => self.won += 1,
+            std::cmp::Ordering::Equal => self.drawn += 1,
+            std::cmp::Ordering::Less => self.lost += 1,
         }
     }
+}
 
-    fn points_against(&self, against: &Throw) -> i32 {
-        let mine = Throw::to_achieve(against, self);
-        mine.points_against(against)
+/// Play every pair of bots against each other once, best first
+fn tournament(game: &Game, rounds: usize, seed: u64) -> Vec<Standing> {
+    let mut players = roster(game, seed);
+    let mut table: Vec<Standing> = players
+        .iter()
+        .map(|p| Standing {
+            name: p.name(game),
+            ..Standing::default()
+        })
+        .collect();
+    for i in 0..table.len() {
+        for j in i + 1..table.len() {
+            let (left, right) = players.split_at_mut(j);
+            let (a, b) = (left[i].as_mut(), right[0].as_mut());
+            a.reset();
+            b.reset();
+            let (a_points, b_points) = play_match(game, a, b, rounds);
+            log::debug!(
+                "{} {} - {} {}",
+                table[i].name,
+                a_points,
+                b_points,
+                table[j].name
+            );
+            table[i].record(a_points, b_points);
+            table[j].record(b_points, a_points);
+        }
     }
+    table.sort_by_key(|s| std::cmp::Reverse((s.league_points(), s.score)));
+    table
+}
+
+fn print_table(table: &[Standing]) {
+    let width = table.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    println!(
+        "{:>3}  {:width$}  {:>2} {:>2} {:>2} {:>2} {:>4} {:>8}",
+        "#", "Bot", "P", "W", "D", "L", "Pts", "Score"
+    );
+    for (place, s) in table.iter().enumerate() {
+        println!(
+            "{:>3}  {:width$}  {:>2} {:>2} {:>2} {:>2} {:>4} {:>8}",
+            place + 1,
+            s.name,
+            s.played(),
+            s.won,
+            s.drawn,
+            s.lost,
+            s.league_points(),
+            s.score
+        );
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[group(id = "day2")]
+#[command(next_help_heading = "Day 2")]
+pub struct Options {
+    /// The game the guide is for: `rps`, `rpsls` or a file with one
+    /// `name theirs ours points` line per throw, in cycle order
+    #[arg(long, value_name = "GAME", default_value = "rps")]
+    game: String,
+
+    /// Score every reading of our column as throws or outcomes, then report the
+    /// `max` or `min` scoring one, or those scoring exactly the given total
+    #[arg(long, value_name = "PICK", num_args = 0..=1, default_missing_value = "max")]
+    cipher: Option<Pick>,
+
+    /// Model the opponent's throws and compare the guide with the best responses to them
+    #[arg(long, conflicts_with = "cipher")]
+    counter: bool,
+
+    /// Ignore the guide and play a round-robin tournament between the built-in
+    /// bots, with this many rounds per match (at most a million)
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        conflicts_with_all = ["cipher", "counter"],
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_ROUNDS as u64)
+    )]
+    tournament: Option<usize>,
+
+    /// Seed for the tournament's random bot
+    #[arg(long, default_value_t = 2022, requires = "tournament")]
+    seed: u64,
+
+    /// Show every round with its points and a running total, then the wins,
+    /// draws and losses for each of our throws
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "text",
+        conflicts_with_all = ["cipher", "counter", "tournament"]
+    )]
+    report: Option<ReportFormat>,
+}
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    score(&Game::rock_paper_scissors(), input, part).map(|points| points.to_string())
+}
+
+pub fn day_main(filename: &str, part: u8, options: &Options) -> Result<(), ParseError> {
+    let game = Game::load(&options.game)?;
+    log::debug!(
+        "Playing {}",
+        game.throws()
+            .map(|t| game.name(t))
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+    if let Some(pick) = options.cipher {
+        return decode(&game, filename, pick);
+    }
+    if let Some(rounds) = options.tournament {
+        print_table(&tournament(&game, rounds, options.seed));
+        return Ok(());
+    }
+    if options.counter {
+        return counter(&game, filename, part);
+    }
+    let plays = plays(&game, &mut parse::open(filename)?, part)?;
+    if let Some(format) = options.report {
+        report(&game, &plays, format, &mut io::stdout().lock())?;
+        if format == ReportFormat::Csv {
+            return Ok(());
+        }
+        println!();
+    }
+    let points: i32 = plays.iter().map(|play| play.points(&game)).sum();
+    println!("Result is {} points", points);
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const ROCK: Throw = Throw(0);
+    const PAPER: Throw = Throw(1);
+    const SCISSORS: Throw = Throw(2);
+
     #[test]
     fn construct_throws() {
-        assert_eq!(Throw::from_str("A"), Throw::Rock);
-        assert_eq!(Throw::from_str("Y"), Throw::Paper);
-        assert_eq!(Throw::from_str("C"), Throw::Scissors);
+        let game = Game::rock_paper_scissors();
+        assert_eq!(Throw::from_str(&game, "A"), ROCK);
+        assert_eq!(Throw::from_str(&game, "Y"), PAPER);
+        assert_eq!(Throw::from_str(&game, "C"), SCISSORS);
     }
 
     #[test]
     fn rock_over_scissors() {
-        assert_eq!(Throw::Rock.against(&Throw::Scissors), Outcome::Win);
-        assert_eq!(Throw::Rock.points_against(&Throw::Scissors), 7);
-        assert_eq!(Outcome::Win.points_against(&Throw::Scissors), 7);
+        let game = Game::rock_paper_scissors();
+        assert_eq!(ROCK.against(&game, &SCISSORS), Outcome::Win);
+        assert_eq!(ROCK.points_against(&game, &SCISSORS), 7);
+        assert_eq!(Outcome::Win.points_against(&game, &SCISSORS), 7);
     }
 
     #[test]
     fn rock_under_paper() {
-        assert_eq!(Throw::Rock.against(&Throw::Paper), Outcome::Lose);
-        assert_eq!(Throw::Rock.points_against(&Throw::Paper), 1);
-        assert_eq!(Outcome::Lose.points_against(&Throw::Paper), 1);
+        let game = Game::rock_paper_scissors();
+        assert_eq!(ROCK.against(&game, &PAPER), Outcome::Lose);
+        assert_eq!(ROCK.points_against(&game, &PAPER), 1);
+        assert_eq!(Outcome::Lose.points_against(&game, &PAPER), 1);
     }
 
     #[test]
     fn rock_opposite_rock() {
-        assert_eq!(Throw::Rock.against(&Throw::Rock), Outcome::Draw);
-        assert_eq!(Throw::Rock.points_against(&Throw::Rock), 4);
-        assert_eq!(Outcome::Draw.points_against(&Throw::Rock), 4);
+        let game = Game::rock_paper_scissors();
+        assert_eq!(ROCK.against(&game, &ROCK), Outcome::Draw);
+        assert_eq!(ROCK.points_against(&game, &ROCK), 4);
+        assert_eq!(Outcome::Draw.points_against(&game, &ROCK), 4);
     }
-}
 
-fn strategy(part: u8, hint: &str, theirs: &Throw) -> i32 {
-    match part {
-        1 => Throw::from_str(hint).points_against(&theirs),
-        2 => Outcome::from_str(hint).points_against(&theirs),
-        _ => panic!("unimplemented part"),
+    #[test]
+    fn each_throw_beats_half_the_cycle() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let throw = |name: &str| game.throws().find(|&t| game.name(t) == name).unwrap();
+        let (rock, spock, paper) = (throw("Rock"), throw("Spock"), throw("Paper"));
+        let (lizard, scissors) = (throw("Lizard"), throw("Scissors"));
+        assert_eq!(rock.against(&game, &lizard), Outcome::Win);
+        assert_eq!(rock.against(&game, &scissors), Outcome::Win);
+        assert_eq!(rock.against(&game, &paper), Outcome::Lose);
+        assert_eq!(rock.against(&game, &spock), Outcome::Lose);
+        assert_eq!(lizard.against(&game, &spock), Outcome::Win);
+        for mine in game.throws() {
+            let wins = game.throws().filter(|&t| game.beats(mine, t)).count();
+            assert_eq!(wins, 2);
+        }
+        // Spock (2) and Paper (3) both beat Rock; Paper scores more
+        assert_eq!(Throw::to_achieve(&game, &rock, &Outcome::Win), paper);
     }
-}
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = File::open(filename).expect("couldn't open file");
-    let reader = BufReader::new(f);
-    let mut points = 0;
-    for line in reader.lines() {
-        if let Ok(items) = line {
-            let (first, second) = items.split_once(" ").expect("Invalid line received");
-            let theirs = Throw::from_str(first);
-            points += strategy(part, second, &theirs)
+    #[test]
+    fn reads_game_definition() {
+        let config = "# name theirs ours points\nRock A X 1\n\nPaper B Y 2\nScissors C Z 3\n";
+        let game = Game::read(config.as_bytes()).unwrap();
+        assert_eq!(game.kinds, Game::rock_paper_scissors().kinds);
+
+        let input = "A Y\nB X\nC Z\n";
+        assert_eq!(score(&game, &mut input.as_bytes(), 1).unwrap(), 15);
+        assert_eq!(score(&game, &mut input.as_bytes(), 2).unwrap(), 12);
+    }
+
+    #[test]
+    fn rejects_even_games_and_reused_codes() {
+        assert!(Game::read("Rock A X 1\nPaper B Y 2\n".as_bytes()).is_err());
+        match Game::read("Rock A X 1\nPaper B Y 2\nScissors C A 3\n".as_bytes()) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (3, 12)),
+            other => panic!("unexpected {:?}", other),
+        }
+        match Game::read("Rock A X 2147483647\nPaper B Y 2\nScissors C Z 3\n".as_bytes()) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (1, 10)),
+            other => panic!("unexpected {:?}", other),
         }
     }
-    println!("Result is {} points", points);
+
+    #[test]
+    fn tries_every_decoding() {
+        let game = Game::rock_paper_scissors();
+        let found = ciphers(&game);
+        assert_eq!(found.len(), 12);
+
+        let rounds = read_guide(&game, &mut "A Y\nB X\nC Z\n".as_bytes()).unwrap();
+        let scored: Vec<(Cipher, i32)> = found
+            .into_iter()
+            .map(|c| {
+                let points = c.score(&game, &rounds).unwrap();
+                (c, points)
+            })
+            .collect();
+        let described = |pick| -> Vec<(String, i32)> {
+            pick_ciphers(&scored, pick)
+                .into_iter()
+                .map(|(c, points)| (c.describe(&game), *points))
+                .collect()
+        };
+        assert!(described(Pick::Target(15)).contains(&("X=Rock Y=Paper Z=Scissors".to_owned(), 15)));
+        assert!(described(Pick::Target(12)).contains(&("X=Lose Y=Draw Z=Win".to_owned(), 12)));
+        // Y is paper against rock, X scissors against paper, Z rock against scissors
+        assert_eq!(
+            described(Pick::Max),
+            vec![("X=Scissors Y=Paper Z=Rock".to_owned(), 24)]
+        );
+        assert!(described(Pick::Target(1000)).is_empty());
+    }
+
+    #[test]
+    fn parses_picks() {
+        assert_eq!("max".parse(), Ok(Pick::Max));
+        assert_eq!("min".parse(), Ok(Pick::Min));
+        assert_eq!("15".parse(), Ok(Pick::Target(15)));
+        assert!("most".parse::<Pick>().is_err());
+        assert_eq!(Pick::Target(13).to_string(), "a score of 13");
+    }
+
+    #[test]
+    fn best_response_to_frequencies() {
+        let game = Game::rock_paper_scissors();
+        let (mine, expected) = best_response(&game, &[0.5, 0.25, 0.25]);
+        // Paper: 8 half the time, 5 a quarter, 2 a quarter
+        assert_eq!(mine, PAPER);
+        assert!((expected - 5.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn follows_transitions() {
+        let game = Game::rock_paper_scissors();
+        let throws = [ROCK, PAPER, ROCK, PAPER, ROCK, SCISSORS];
+        let model = OpponentModel::observe(&game, &throws);
+        assert_eq!(model.after(PAPER), Some(vec![1.0, 0.0, 0.0]));
+        assert_eq!(model.after(SCISSORS), None);
+
+        let (responses, _) = transition_responses(&game, &model, &throws);
+        // Rock is their favourite; after Rock they mostly throw Paper, after Paper always Rock
+        assert_eq!(responses[0], PAPER);
+        assert_eq!(responses[1], SCISSORS);
+        assert_eq!(responses[2], PAPER);
+    }
+
+    #[test]
+    fn bots_see_the_history() {
+        let game = Game::rock_paper_scissors();
+        let history = [(ROCK, PAPER), (ROCK, SCISSORS)];
+        assert_eq!(CopyLast.throw(&game, &history), SCISSORS);
+        assert_eq!(BeatLast.throw(&game, &history), ROCK);
+        assert_eq!(Cycling.throw(&game, &history), SCISSORS);
+        let history = [(ROCK, PAPER), (ROCK, PAPER), (ROCK, ROCK)];
+        let mut counter = FrequencyCounter::default();
+        for &(ours, theirs) in &history {
+            counter.observe(&game, ours, theirs);
+        }
+        assert_eq!(counter.throw(&game, &history), SCISSORS);
+        counter.reset();
+        assert_eq!(counter.throw(&game, &[]), ROCK);
+    }
+
+    #[test]
+    fn random_bot_follows_its_seed() {
+        let game = Game::rock_paper_scissors();
+        let throws =
+            |bot: &mut Random| -> Vec<Throw> { (0..20).map(|_| bot.throw(&game, &[])).collect() };
+        assert_eq!(throws(&mut Random::new(7)), throws(&mut Random::new(7)));
+        assert_ne!(throws(&mut Random::new(7)), throws(&mut Random::new(8)));
+        // Seed 0 has a sequence of its own rather than a degenerate one
+        let mut zero = Random::new(0);
+        let first = throws(&mut zero);
+        assert_ne!(first, throws(&mut Random::new(1)));
+        zero.reset();
+        assert_eq!(throws(&mut zero), first);
+        assert_eq!(tournament(&game, 30, 0), tournament(&game, 30, 0));
+    }
+
+    #[test]
+    fn round_robin_table() {
+        let game = Game::rock_paper_scissors();
+        let table = tournament(&game, 30, 1);
+        let bots = table.len();
+        assert_eq!(bots, 8);
+        for standing in &table {
+            assert_eq!(standing.played(), bots - 1);
+        }
+        let wins: usize = table.iter().map(|s| s.won).sum();
+        let losses: usize = table.iter().map(|s| s.lost).sum();
+        assert_eq!(wins, losses);
+
+        // Beat last punishes every fixed bot after the first round
+        let mut rock = Fixed(ROCK);
+        let (fixed, beat) = play_match(&game, &mut rock, &mut BeatLast, 10);
+        assert_eq!((fixed, beat), (4 + 9, 4 + 9 * 8));
+    }
+
+    #[test]
+    fn reports_each_round() {
+        let game = Game::rock_paper_scissors();
+        let plays = plays(&game, &mut "A Y\nB X\nC Z\n".as_bytes(), 2).unwrap();
+        assert_eq!(tally(&game, &plays), vec![[1, 1, 1], [0, 0, 0], [0, 0, 0]]);
+
+        let mut csv: Vec<u8> = Vec::new();
+        report(&game, &plays, ReportFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "record,line,theirs,ours,outcome,throw_points,outcome_points,total",
+                "round,1,Rock,Rock,Draw,1,3,4",
+                "round,2,Paper,Rock,Lose,1,0,5",
+                "round,3,Scissors,Rock,Win,1,6,12",
+                "record,throw,wins,draws,losses",
+                "tally,Rock,1,1,1",
+                "tally,Paper,0,0,0",
+                "tally,Scissors,0,0,0",
+            ]
+        );
+    }
 }
@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::parse::{self, Line, ParseError};
+use std::collections::BTreeMap;
+use std::io::BufRead;
 
 fn char_value(c: char) -> i32 {
     match c {
@@ -10,79 +10,537 @@ fn char_value(c: char) -> i32 {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The item types a–zA–Z as a set of bits, each item at the bit numbered by its priority
+type Items = u64;
 
-    #[test]
-    fn char_values() {
-        assert_eq!(char_value('a'), 1);
-        assert_eq!(char_value('z'), 26);
-        assert_eq!(char_value('A'), 27);
-        assert_eq!(char_value('Z'), 52);
+fn items(contents: &str) -> Items {
+    contents.chars().fold(0, |set, c| match char_value(c) {
+        0 => set,
+        priority => set | 1 << priority,
+    })
+}
+
+/// The item whose priority is `priority`
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + (priority - 1) as u8) as char,
+        27..=52 => (b'A' + (priority - 27) as u8) as char,
+        _ => panic!("No item has priority {}", priority),
     }
 }
 
-pub fn day_main(filename: &str, part: u8) {
-    if part == 1 {
-        part1_main(filename)
-    } else {
-        part1_main(filename)
+/// The priorities of every item in the set, lowest first
+fn priorities_of(mut items: Items) -> impl Iterator<Item = u32> {
+    std::iter::from_fn(move || {
+        if items == 0 {
+            return None;
+        }
+        let priority = items.trailing_zeros();
+        items &= items - 1;
+        Some(priority)
+    })
+}
+
+fn total_priority(items: Items) -> i32 {
+    priorities_of(items).map(|p| p as i32).sum()
+}
+
+/// A rucksack's contents split into its two compartments
+struct Rucksack {
+    left: Items,
+    right: Items,
+}
+
+impl Rucksack {
+    fn new(contents: &str) -> Rucksack {
+        let middle = compartment_split(contents);
+        Rucksack {
+            left: items(&contents[..middle]),
+            right: items(&contents[middle..]),
+        }
+    }
+
+    /// Item types found in both compartments
+    fn misplaced(&self) -> Items {
+        self.left & self.right
+    }
+
+    fn all(&self) -> Items {
+        self.left | self.right
+    }
+}
+
+/// Where the second compartment starts, backing off to a character boundary for odd input
+fn compartment_split(contents: &str) -> usize {
+    (0..=contents.len() / 2)
+        .rev()
+        .find(|&at| contents.is_char_boundary(at))
+        .unwrap_or(0)
+}
+
+/// Complain about contents that the compartments and priorities can't make sense of
+fn check(line: &Line) {
+    if line.text.chars().count() % 2 == 1 {
+        log::warn!(
+            "line {}: {} items can't be split evenly between compartments",
+            line.number,
+            line.text.chars().count()
+        );
+    }
+    for (at, c) in line.text.char_indices() {
+        if char_value(c) == 0 {
+            log::warn!(
+                "line {}, column {}: `{}` isn't an item and has no priority",
+                line.number,
+                at + 1,
+                c
+            );
+        }
     }
 }
 
-pub fn part1_main(filename: &str) {
-    let f = File::open(filename).expect("Couldn't open file");
-    let reader = BufReader::new(f);
+/// An item found in both compartments, with its 1-based positions within each
+#[derive(Debug, PartialEq, Eq)]
+struct Misplaced {
+    item: char,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+fn positions(compartment: &str, item: char) -> Vec<usize> {
+    compartment
+        .chars()
+        .enumerate()
+        .filter(|&(_, c)| c == item)
+        .map(|(at, _)| at + 1)
+        .collect()
+}
+
+fn misplaced_items(contents: &str) -> Vec<Misplaced> {
+    let (left, right) = contents.split_at(compartment_split(contents));
+    priorities_of(Rucksack::new(contents).misplaced())
+        .map(item)
+        .map(|item| Misplaced {
+            item,
+            left: positions(left, item),
+            right: positions(right, item),
+        })
+        .collect()
+}
+
+fn list(positions: &[usize]) -> String {
+    positions
+        .iter()
+        .map(|at| at.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Every misplaced item in every rucksack, with the total of their priorities
+fn report(input: &mut dyn BufRead) -> Result<i32, ParseError> {
     let mut priority_sum = 0;
-    let mut middle: usize;
-    let mut right_contents: HashSet<char>;
-    let mut left_contents: HashSet<char>;
-
-    for line_result in reader.lines() {
-        if let Ok(line) = line_result {
-            middle = line.len() / 2;
-            right_contents = line[..middle].chars().collect();
-            left_contents = line[middle..].chars().collect();
-            for c in right_contents.intersection(&left_contents) {
-                priority_sum += char_value(*c);
+    for line in parse::lines(input) {
+        let line = line?;
+        check(&line);
+        let misplaced = misplaced_items(&line.text);
+        if misplaced.is_empty() {
+            println!("line {}: nothing misplaced", line.number);
+        }
+        for found in misplaced {
+            priority_sum += char_value(found.item);
+            println!(
+                "line {}: `{}` (priority {}) at {} in the left compartment and {} in the right",
+                line.number,
+                found.item,
+                char_value(found.item),
+                list(&found.left),
+                list(&found.right)
+            );
+        }
+    }
+    Ok(priority_sum)
+}
+
+/// Swaps between compartments, by 0-based position in each, that leave no item type in
+/// both, along with the contents once they're done
+#[derive(Debug, PartialEq, Eq)]
+struct Plan {
+    swaps: Vec<(usize, usize)>,
+    layout: String,
+}
+
+/// Swaps, then priority of the items moved, so cheap items move before precious ones
+type Cost = (usize, i32);
+
+fn cheaper(slot: &mut Option<Cost>, cost: Cost) {
+    if slot.is_none_or(|best| cost < best) {
+        *slot = Some(cost);
+    }
+}
+
+/// The fewest swaps that make the compartments disjoint, or `None` when no choice of
+/// whole item types exactly fills the first compartment
+fn plan(contents: &str) -> Option<Plan> {
+    let (left, right) = contents.split_at(compartment_split(contents));
+    let mut left: Vec<char> = left.chars().collect();
+    let mut right: Vec<char> = right.chars().collect();
+    let mut counts: BTreeMap<(i32, char), (usize, usize)> = BTreeMap::new();
+    for &c in &left {
+        counts.entry((char_value(c), c)).or_default().0 += 1;
+    }
+    for &c in &right {
+        counts.entry((char_value(c), c)).or_default().1 += 1;
+    }
+    let kinds: Vec<(i32, char, usize, usize)> = counts
+        .into_iter()
+        .map(|((priority, c), (l, r))| (priority, c, l, r))
+        .collect();
+
+    // best[i][n]: the cheapest way to put the first i kinds wholly in one compartment
+    // or the other, with n items in the first
+    let target = left.len();
+    let mut best: Vec<Vec<Option<Cost>>> = vec![vec![None; target + 1]; kinds.len() + 1];
+    best[0][0] = Some((0, 0));
+    for (i, &(priority, _, l, r)) in kinds.iter().enumerate() {
+        for n in 0..=target {
+            let Some((swaps, moved)) = best[i][n] else {
+                continue;
+            };
+            cheaper(&mut best[i + 1][n], (swaps, moved + l as i32 * priority));
+            if n + l + r <= target {
+                cheaper(
+                    &mut best[i + 1][n + l + r],
+                    (swaps + r, moved + r as i32 * priority),
+                );
+            }
+        }
+    }
+    best[kinds.len()][target]?;
+
+    let mut in_left: Vec<char> = Vec::new();
+    let mut n = target;
+    for (i, &(priority, c, l, r)) in kinds.iter().enumerate().rev() {
+        let (swaps, moved) = best[i + 1][n].expect("Only reachable states are followed");
+        let stays_left = n >= l + r
+            && swaps >= r
+            && best[i][n - l - r] == Some((swaps - r, moved - r as i32 * priority));
+        if stays_left {
+            in_left.push(c);
+            n -= l + r;
+        }
+    }
+
+    let outgoing = left
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !in_left.contains(c))
+        .map(|(at, _)| at);
+    let incoming = right
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| in_left.contains(c))
+        .map(|(at, _)| at);
+    let swaps: Vec<(usize, usize)> = outgoing.zip(incoming).collect();
+    for &(l, r) in &swaps {
+        std::mem::swap(&mut left[l], &mut right[r]);
+    }
+    Some(Plan {
+        swaps,
+        layout: left.iter().chain(right.iter()).collect(),
+    })
+}
+
+/// A plan for every rucksack, returning how many swaps they need altogether
+fn rearrange(input: &mut dyn BufRead) -> Result<usize, ParseError> {
+    let mut total = 0;
+    let mut unfixable = 0;
+    for line in parse::lines(input) {
+        let line = line?;
+        match plan(&line.text) {
+            Some(plan) => {
+                let swaps: Vec<String> = plan
+                    .swaps
+                    .iter()
+                    .map(|(l, r)| format!("left {} <-> right {}", l + 1, r + 1))
+                    .collect();
+                println!(
+                    "line {}: {} swap(s) [{}] gives {}",
+                    line.number,
+                    plan.swaps.len(),
+                    swaps.join(", "),
+                    plan.layout
+                );
+                total += plan.swaps.len();
             }
-            right_contents.clear();
-            left_contents.clear();
+            None => {
+                println!(
+                    "line {}: can't be fixed, no set of whole item types fills the first compartment",
+                    line.number
+                );
+                unfixable += 1;
+            }
+        }
+    }
+    if unfixable > 0 {
+        log::warn!("{} rucksack(s) can't be fixed by swapping", unfixable);
+    }
+    Ok(total)
+}
+
+const GROUP_SIZE: usize = 3;
+
+#[derive(clap::Args, Debug)]
+#[group(id = "day3")]
+#[command(next_help_heading = "Day 3")]
+pub struct Options {
+    /// Number of elves sharing a badge in part 2
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = GROUP_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    group_size: usize,
+
+    /// List every misplaced item in part 1, with where it is in each compartment
+    #[arg(long)]
+    misplaced: bool,
+
+    /// Plan the fewest swaps between compartments that leave no item type in both
+    #[arg(long, conflicts_with = "misplaced")]
+    rearrange: bool,
+}
+
+impl Options {
+    /// Complain about options that don't apply to `part`, which clap can't check
+    pub fn check(&self, part: u8) -> Result<(), String> {
+        if self.misplaced && part != 1 {
+            return Err("the argument '--misplaced' only applies to part 1".to_owned());
         }
+        Ok(())
+    }
+}
+
+fn priorities(input: &mut dyn BufRead, part: u8, group_size: usize) -> Result<i32, ParseError> {
+    if part == 1 {
+        part1_main(input)
+    } else {
+        part2_main(input, group_size)
     }
+}
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    priorities(input, part, GROUP_SIZE).map(|priority_sum| priority_sum.to_string())
+}
+
+pub fn day_main(filename: &str, part: u8, options: &Options) -> Result<(), ParseError> {
+    let input = &mut parse::open(filename)?;
+    if options.rearrange {
+        println!("Swaps needed: {}", rearrange(input)?);
+        return Ok(());
+    }
+    let priority_sum = if options.misplaced {
+        report(input)?
+    } else {
+        priorities(input, part, options.group_size)?
+    };
     println!("Cumulative priorities: {}", priority_sum);
+    Ok(())
 }
 
-pub fn part2_main(filename: &str) {
-    let f = File::open(filename).expect("Couldn't open file");
-    let mut lines = BufReader::new(f).lines();
+pub fn part1_main(input: &mut dyn BufRead) -> Result<i32, ParseError> {
     let mut priority_sum = 0;
-    let mut contents: HashSet<char>;
-    let mut count: HashMap<char, usize> = HashMap::with_capacity(32);
+    let checking = log::log_enabled!(log::Level::Warn);
+    for line in parse::lines(input) {
+        let line = line?;
+        if checking {
+            check(&line);
+        }
+        priority_sum += total_priority(Rucksack::new(&line.text).misplaced());
+    }
+    Ok(priority_sum)
+}
+
+/// `lines 4-6`, for errors about a whole group
+fn line_range(group: &[Line]) -> String {
+    match group {
+        [only] => format!("line {}", only.number),
+        [first, .., last] => format!("lines {}-{}", first.number, last.number),
+        [] => "no lines".to_owned(),
+    }
+}
+
+fn group_error(group: &[Line], message: String) -> ParseError {
+    ParseError::at_line(
+        group.first().map_or(1, |line| line.number),
+        format!("group at {} {}", line_range(group), message),
+    )
+}
+
+/// The one item carried by every rucksack in the group
+fn badge(group: &[Line]) -> Result<char, ParseError> {
+    let common = group.iter().fold(Items::MAX, |common, ruck| {
+        common & Rucksack::new(&ruck.text).all()
+    });
+    match common.count_ones() {
+        1 => Ok(item(common.trailing_zeros())),
+        0 => Err(group_error(group, "has no item in common".to_owned())),
+        several => Err(group_error(
+            group,
+            format!(
+                "has {} items in common: {}",
+                several,
+                priorities_of(common).map(item).collect::<String>()
+            ),
+        )),
+    }
+}
+
+pub fn part2_main(input: &mut dyn BufRead, group_size: usize) -> Result<i32, ParseError> {
+    let mut lines = parse::lines(input);
+    let mut priority_sum = 0;
+    let mut errors: Vec<ParseError> = Vec::new();
+    let mut group: Vec<Line> = Vec::with_capacity(group_size);
 
     loop {
-        if let Some(Ok(first)) = lines.next() {
-            let second = lines.next().unwrap().unwrap();
-            let third = lines.next().unwrap().unwrap();
-            for ruck in vec![first, second, third] {
-                contents = ruck.chars().collect();
-                for c in &contents {
-                    count.entry(*c).and_modify(|e| *e += 1).or_insert(1);
-                }
-                contents.clear();
+        group.clear();
+        for line in lines.by_ref().take(group_size) {
+            group.push(line?);
+        }
+        if group.is_empty() {
+            break;
+        }
+        if group.len() < group_size {
+            errors.push(group_error(
+                &group,
+                format!("has {} of {} rucksacks", group.len(), group_size),
+            ));
+            break;
+        }
+        match badge(&group) {
+            Ok(c) => {
+                log::trace!("The common char is {}", c);
+                priority_sum += char_value(c);
             }
-            for (c, v) in count.iter() {
-                if *v == 3 {
-                    // println!("The common char is {}", c);
-                    priority_sum += char_value(*c);
-                    break;
-                }
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(priority_sum)
+    } else {
+        Err(ParseError::combine(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_values() {
+        assert_eq!(char_value('a'), 1);
+        assert_eq!(char_value('z'), 26);
+        assert_eq!(char_value('A'), 27);
+        assert_eq!(char_value('Z'), 52);
+    }
+
+    #[test]
+    fn items_as_bits() {
+        let set = items("vJrwpWtwJgWr");
+        assert_eq!(set.count_ones(), 8);
+        assert_eq!(priorities_of(set).map(item).collect::<String>(), "gprtvwJW");
+        let ruck = Rucksack::new("vJrwpWtwJgWrhcsFMMfFFhFp");
+        assert_eq!(ruck.misplaced(), 1 << char_value('p'));
+        assert_eq!(item(ruck.misplaced().trailing_zeros()), 'p');
+        assert_eq!(part1_main(&mut EXAMPLE.as_bytes()).unwrap(), 157);
+    }
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    #[test]
+    fn badges_per_group() {
+        assert_eq!(part2_main(&mut EXAMPLE.as_bytes(), 3).unwrap(), 70);
+        assert_eq!(solve(&mut EXAMPLE.as_bytes(), 2).unwrap(), "70");
+    }
+
+    #[test]
+    fn reports_bad_groups_by_line_range() {
+        match part2_main(&mut EXAMPLE.as_bytes(), 4) {
+            Err(ParseError::Many(errors)) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                assert_eq!(
+                    messages,
+                    vec![
+                        "line 1, column 1: group at lines 1-4 has no item in common",
+                        "line 5, column 1: group at lines 5-6 has 2 of 4 rucksacks",
+                    ]
+                );
             }
-            count.clear();
-        } else {
-            break;
+            other => panic!("unexpected {:?}", other),
+        }
+        match part2_main(&mut "abc\ncab\n".as_bytes(), 2) {
+            Err(e) => assert!(e.to_string().ends_with("has 3 items in common: abc")),
+            other => panic!("unexpected {:?}", other),
         }
     }
-    println!("Cumulative priorities: {}", priority_sum);
+
+    #[test]
+    fn misplaced_is_only_for_part_1() {
+        let options = Options {
+            group_size: GROUP_SIZE,
+            misplaced: true,
+            rearrange: false,
+        };
+        assert!(options.check(1).is_ok());
+        assert!(options.check(2).is_err());
+    }
+
+    #[test]
+    fn locates_misplaced_items() {
+        assert_eq!(
+            misplaced_items("vJrwpWtwJgWrhcsFMMfFFhFp"),
+            vec![Misplaced {
+                item: 'p',
+                left: vec![5],
+                right: vec![12],
+            }]
+        );
+        assert_eq!(
+            misplaced_items("abaXbb"),
+            vec![Misplaced {
+                item: 'b',
+                left: vec![2],
+                right: vec![2, 3],
+            },]
+        );
+        // An odd item out goes in the second compartment, and non-items are never misplaced
+        assert_eq!(misplaced_items("a-b-a")[0].right, vec![3]);
+        assert_eq!(Rucksack::new("é-ab-é").misplaced(), 0);
+    }
+
+    #[test]
+    fn plans_fewest_swaps() {
+        // p could go either way; swapping it for c moves the cheapest items
+        let found = plan("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        assert_eq!(found.swaps, vec![(4, 1)]);
+        assert_eq!(found.layout, "vJrwcWtwJgWrhpsFMMfFFhFp");
+        assert_eq!(Rucksack::new(&found.layout).misplaced(), 0);
+
+        let found = plan("abcbcd").unwrap();
+        assert_eq!(found.swaps.len(), 1);
+        assert_eq!(Rucksack::new(&found.layout).misplaced(), 0);
+        assert_eq!(plan("abcdef").unwrap().swaps, vec![]);
+    }
+
+    #[test]
+    fn flags_unfixable_rucksacks() {
+        // Three a's can't fit in a compartment of two, nor can the one b fill it
+        assert_eq!(plan("aaab"), None);
+    }
 }
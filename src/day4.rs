@@ -1,75 +1,605 @@
-use std::io::{BufRead, BufReader};
+use crate::parse::{self, FromLine, Line, ParseError};
+use std::io::{self, BufRead, Write};
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = std::fs::File::open(filename).expect("Couldn't open file");
-    let lines = BufReader::new(f).lines();
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
     let mut count = 0;
 
-    for line in lines {
-        if let Ok(line) = line {
-            if let Some(pair) = Pairing::from_str(&line) {
-                if part == 1 && pair.one_encompasses_another() {
-                    count += 1;
-                    println!("Encompassing: {:?}", pair);
-                } else if part == 2 && pair.one_overlaps_another() {
-                    count += 1;
-                    println!("Overlapping: {:?}", pair);
-                }
+    for group in parse::parse_lines::<Group, _>(input) {
+        let group = group?;
+        if part == 1 && group.one_encompasses_another() {
+            count += 1;
+            log::debug!("Encompassing: {:?}", group);
+        } else if part == 2 && group.one_overlaps_another() {
+            count += 1;
+            log::debug!("Overlapping: {:?}", group);
+        }
+    }
+    Ok(count.to_string())
+}
+
+#[derive(clap::Args, Debug)]
+#[group(id = "day4")]
+#[command(next_help_heading = "Day 4")]
+pub struct Options {
+    /// Say which members of each counted group conflict
+    #[arg(long)]
+    conflicts: bool,
+
+    /// Instead of counting groups, show which sections are left unassigned, which
+    /// are cleaned most, and how many elves clean each section
+    #[arg(long, conflicts_with = "conflicts")]
+    coverage: bool,
+
+    /// Instead of counting groups, list every pair of assignments anywhere in the
+    /// input that overlap, noting those where one contains the other
+    #[arg(long, conflicts_with_all = ["conflicts", "coverage"])]
+    all_pairs: bool,
+}
+
+/// Why each counted group counts, with members numbered from 1 in line order
+fn report_conflicts(input: &mut dyn BufRead, part: u8) -> Result<usize, ParseError> {
+    let mut count = 0;
+    for line in parse::lines(input) {
+        let line = line?;
+        let group = Group::from_line(&line)?;
+        let member = |i: usize| {
+            let a = &group.members[i];
+            format!("#{} ({}-{})", i + 1, a.start, a.end)
+        };
+        let conflicts: Vec<String> = if part == 1 {
+            group
+                .containments()
+                .map(|(inner, outer)| format!("{} is inside {}", member(inner), member(outer)))
+                .collect()
+        } else {
+            group
+                .overlaps()
+                .map(|(a, b)| format!("{} overlaps {}", member(a), member(b)))
+                .collect()
+        };
+        if !conflicts.is_empty() {
+            count += 1;
+            println!("line {}: {}", line.number, conflicts.join(", "));
+        }
+    }
+    Ok(count)
+}
+
+const CHART_WIDTH: usize = 60;
+
+/// Runs of consecutive sections cleaned by the same number of elves, from the first
+/// assigned section to the last, found by sweeping over where assignments start and end.
+/// An assignment running up to `usize::MAX` never ends, so the last run goes that far.
+fn coverage(assignments: &[Assignment]) -> Vec<(Assignment, usize)> {
+    let mut events: Vec<(usize, isize)> = assignments
+        .iter()
+        .flat_map(|a| {
+            let after = a.end.checked_add(1).map(|after| (after, -1));
+            [Some((a.start, 1)), after].into_iter().flatten()
+        })
+        .collect();
+    events.sort_unstable();
+    let mut runs: Vec<(Assignment, usize)> = Vec::new();
+    let mut depth: isize = 0;
+    for (i, &(at, change)) in events.iter().enumerate() {
+        depth += change;
+        match events.get(i + 1) {
+            Some(&(next, _)) if next > at => {
+                runs.push((Assignment::new(at, next - 1), depth as usize));
             }
+            None if depth > 0 => runs.push((Assignment::new(at, usize::MAX), depth as usize)),
+            _ => {}
+        }
+    }
+    runs
+}
+
+/// `4` or `2-6`
+fn label(range: &Assignment) -> String {
+    match range.size() {
+        1 => range.start.to_string(),
+        _ => format!("{}-{}", range.start, range.end),
+    }
+}
+
+fn describe(set: &IntervalSet) -> String {
+    let ranges: Vec<String> = set.ranges().iter().map(label).collect();
+    match set.len() {
+        0 => "none".to_owned(),
+        1 => format!("{} (1 section)", ranges[0]),
+        n => format!("{} ({} sections)", ranges.join(", "), n),
+    }
+}
+
+fn coverage_report(input: &mut dyn BufRead) -> Result<(), ParseError> {
+    let groups = parse::collect_all(parse::parse_lines::<Group, _>(input))?;
+    let assignments: Vec<Assignment> = groups.into_iter().flat_map(|g| g.members).collect();
+    let assigned: IntervalSet = assignments.iter().copied().collect();
+    let Some(last) = assigned.ranges().last() else {
+        println!("No sections are assigned");
+        return Ok(());
+    };
+    // Section IDs start at 1, so anything before the first assignment is a gap too
+    let camp = IntervalSet::from(Assignment::new(1.min(assigned.ranges()[0].start), last.end));
+    let runs = coverage(&assignments);
+    let deepest = runs.iter().map(|&(_, depth)| depth).max().unwrap_or(0);
+    let most_cleaned: IntervalSet = runs
+        .iter()
+        .filter(|&&(_, depth)| depth == deepest)
+        .map(|&(range, _)| range)
+        .collect();
+
+    println!("Assigned: {}", describe(&assigned));
+    println!("Unassigned: {}", describe(&camp.difference(&assigned)));
+    println!("Cleaned most: {}", describe(&most_cleaned));
+    println!("Maximum overlap depth: {}", deepest);
+    println!();
+    let width = runs.iter().map(|(r, _)| label(r).len()).max().unwrap_or(0);
+    let scale = deepest.div_ceil(CHART_WIDTH).max(1);
+    for (range, depth) in &runs {
+        println!(
+            "{:>width$} |{} {}",
+            label(range),
+            "#".repeat(depth.div_ceil(scale)),
+            depth
+        );
+    }
+    Ok(())
+}
+
+/// Call `found` with the indices of every overlapping pair, earlier start first. Sweeping
+/// in order of start, only the assignments still running can overlap the next one, so the
+/// cost is the sort plus the number of pairs found.
+fn for_each_overlap(assignments: &[Assignment], mut found: impl FnMut(usize, usize)) {
+    let mut order: Vec<usize> = (0..assignments.len()).collect();
+    order.sort_unstable_by_key(|&i| assignments[i]);
+    let mut running: Vec<usize> = Vec::new();
+    for i in order {
+        let start = assignments[i].start;
+        running.retain(|&j| assignments[j].end >= start);
+        for &j in &running {
+            found(j, i);
+        }
+        running.push(i);
+    }
+}
+
+fn all_pairs_report(input: &mut dyn BufRead) -> Result<(), ParseError> {
+    let mut sides: Vec<(usize, usize)> = Vec::new();
+    let mut assignments: Vec<Assignment> = Vec::new();
+    for line in parse::lines(input) {
+        let line = line?;
+        for (member, assignment) in Group::from_line(&line)?.members.into_iter().enumerate() {
+            sides.push((line.number, member + 1));
+            assignments.push(assignment);
         }
     }
+
+    let out = &mut io::BufWriter::new(io::stdout().lock());
+    let (mut overlapping, mut containing) = (0, 0);
+    let mut written = Ok(());
+    for_each_overlap(&assignments, |i, j| {
+        let (a, b) = (&assignments[i], &assignments[j]);
+        let relation = if a.contains(b) || b.contains(a) {
+            containing += 1;
+            "contains"
+        } else {
+            "overlaps"
+        };
+        overlapping += 1;
+        if written.is_ok() {
+            written = writeln!(
+                out,
+                "line {} #{} ({}) {} line {} #{} ({})",
+                sides[i].0,
+                sides[i].1,
+                label(a),
+                relation,
+                sides[j].0,
+                sides[j].1,
+                label(b)
+            );
+        }
+    });
+    written?;
+    writeln!(
+        out,
+        "{} overlapping pairs, {} where one contains the other",
+        overlapping, containing
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn day_main(filename: &str, part: u8, options: &Options) -> Result<(), ParseError> {
+    if options.all_pairs {
+        return all_pairs_report(&mut parse::open(filename)?);
+    }
+    if options.coverage {
+        return coverage_report(&mut parse::open(filename)?);
+    }
+    let count = if options.conflicts {
+        report_conflicts(&mut parse::open(filename)?, part)?.to_string()
+    } else {
+        solve(&mut parse::open(filename)?, part)?
+    };
     println!("Final count is {}", count);
+    Ok(())
 }
 
-#[derive(Debug)]
-struct Assignment {
+/// The sections `start` to `end`, both included
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Assignment {
     start: usize,
     end: usize,
 }
 
-#[derive(Debug)]
-struct Pairing {
-    a_side: Assignment,
-    b_side: Assignment,
-}
-
-impl Pairing {
-    pub fn new(a_start: usize, a_end: usize, b_start: usize, b_end: usize) -> Self {
-        Self {
-            a_side: Assignment {
-                start: a_start,
-                end: a_end,
-            },
-            b_side: Assignment {
-                start: b_start,
-                end: b_end,
-            },
+impl Assignment {
+    pub fn new(start: usize, end: usize) -> Assignment {
+        assert!(
+            start <= end,
+            "Assignment {}-{} ends before it starts",
+            start,
+            end
+        );
+        Assignment { start, end }
+    }
+
+    /// How many sections there are in the assignment, which is never empty. One
+    /// covering every section from 0 to `usize::MAX` is counted as `usize::MAX`.
+    pub fn size(&self) -> usize {
+        (self.end - self.start).saturating_add(1)
+    }
+
+    pub fn contains(&self, other: &Assignment) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn contains_section(&self, section: usize) -> bool {
+        self.start <= section && section <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Assignment) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Assignment) -> Option<Assignment> {
+        self.overlaps(other)
+            .then(|| Assignment::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+
+    pub fn union(&self, other: &Assignment) -> IntervalSet {
+        [*self, *other].into_iter().collect()
+    }
+
+    pub fn difference(&self, other: &Assignment) -> IntervalSet {
+        IntervalSet::from(*self).difference(&IntervalSet::from(*other))
+    }
+}
+
+/// Sections made up of any number of assignments, kept as sorted ranges that neither
+/// overlap nor touch
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Assignment>,
+}
+
+impl IntervalSet {
+    pub fn ranges(&self) -> &[Assignment] {
+        &self.ranges
+    }
+
+    /// How many sections there are in the set
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(Assignment::size)
+            .fold(0, usize::saturating_add)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn contains_section(&self, section: usize) -> bool {
+        let after = self.ranges.partition_point(|r| r.end < section);
+        self.ranges
+            .get(after)
+            .is_some_and(|r| r.contains_section(section))
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.ranges
+            .iter()
+            .chain(other.ranges.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut found: Vec<Assignment> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            found.extend(a.intersection(b));
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
         }
+        found.into_iter().collect()
     }
 
-    pub fn from_str(s: &str) -> Option<Self> {
-        let (a, b) = s.split_once(",")?;
-        let (a_start, a_end) = a.split_once("-")?;
-        let (b_start, b_end) = b.split_once("-")?;
-        if let (Ok(a_start), Ok(a_end)) = (a_start.parse::<usize>(), a_end.parse::<usize>()) {
-            if let (Ok(b_start), Ok(b_end)) = (b_start.parse::<usize>(), b_end.parse::<usize>()) {
-                return Some(Self::new(a_start, a_end, b_start, b_end));
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut found: Vec<Assignment> = Vec::new();
+        for range in &self.ranges {
+            let mut rest = Some(*range);
+            for cut in other.ranges.iter().filter(|cut| cut.overlaps(range)) {
+                let Some(remaining) = rest else {
+                    break;
+                };
+                if cut.start > remaining.start {
+                    found.push(Assignment::new(remaining.start, cut.start - 1));
+                }
+                rest =
+                    (cut.end < remaining.end).then(|| Assignment::new(cut.end + 1, remaining.end));
             }
+            found.extend(rest);
+        }
+        found.into_iter().collect()
+    }
+}
+
+impl From<Assignment> for IntervalSet {
+    fn from(range: Assignment) -> Self {
+        IntervalSet {
+            ranges: vec![range],
         }
-        None
+    }
+}
+
+impl FromIterator<Assignment> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Assignment>>(iter: I) -> Self {
+        let mut sorted: Vec<Assignment> = iter.into_iter().collect();
+        sorted.sort_unstable();
+        let mut ranges: Vec<Assignment> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end.saturating_add(1) => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => ranges.push(range),
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+/// The assignments of every elf on one line of the input
+#[derive(Debug)]
+struct Group {
+    members: Vec<Assignment>,
+}
+
+impl Group {
+    /// Each pair of members, by index, earlier member first
+    fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.members.len()).flat_map(move |i| (i + 1..self.members.len()).map(move |j| (i, j)))
+    }
+
+    /// (inner, outer) for each member lying within another. Identical members are
+    /// only reported once, as the later inside the earlier.
+    fn containments(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pairs().filter_map(|(i, j)| {
+            let (a, b) = (&self.members[i], &self.members[j]);
+            if a.contains(b) {
+                Some((j, i))
+            } else if b.contains(a) {
+                Some((i, j))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn overlaps(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pairs()
+            .filter(|&(i, j)| self.members[i].overlaps(&self.members[j]))
     }
 
     fn one_encompasses_another(&self) -> bool {
-        // a encompasses b
-        (self.a_side.start <= self.b_side.start && self.a_side.end >= self.b_side.end)
-        // b encompasses a
-            || (self.a_side.start >= self.b_side.start && self.a_side.end <= self.b_side.end)
+        self.containments().next().is_some()
     }
 
     fn one_overlaps_another(&self) -> bool {
-        (self.b_side.start <= self.a_side.start && self.a_side.start <= self.b_side.end)
-            || (self.b_side.start <= self.a_side.end && self.a_side.end <= self.b_side.end)
-            || (self.a_side.start <= self.b_side.start && self.b_side.start <= self.a_side.end)
-            || (self.a_side.start <= self.b_side.end && self.b_side.end <= self.a_side.end)
+        self.overlaps().next().is_some()
+    }
+}
+
+/// `start-end`, complaining if it ends before it starts
+fn assignment(line: &Line, field: &str) -> Result<Assignment, ParseError> {
+    let (start, end) = line.split_once(field, "-")?;
+    let (start, end) = (line.parse(start)?, line.parse(end)?);
+    if end < start {
+        return Err(line.error_at(field, format!("`{}` ends before it starts", field)));
+    }
+    Ok(Assignment::new(start, end))
+}
+
+impl FromLine for Group {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
+        let members = line
+            .text
+            .split(',')
+            .map(|field| assignment(line, field))
+            .collect::<Result<Vec<Assignment>, ParseError>>()?;
+        Ok(Group { members })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn sections(ranges: &[Assignment]) -> BTreeSet<usize> {
+        ranges.iter().flat_map(|r| r.start..=r.end).collect()
+    }
+
+    fn assignments() -> impl Strategy<Value = Assignment> {
+        (0usize..60, 0usize..15).prop_map(|(start, len)| Assignment::new(start, start + len))
+    }
+
+    fn interval_sets() -> impl Strategy<Value = (Vec<Assignment>, IntervalSet)> {
+        prop::collection::vec(assignments(), 0..8).prop_map(|ranges| {
+            let set = ranges.iter().copied().collect();
+            (ranges, set)
+        })
+    }
+
+    #[test]
+    fn example_pairs() {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n";
+        assert_eq!(solve(&mut input.as_bytes(), 1).unwrap(), "2");
+        assert_eq!(solve(&mut input.as_bytes(), 2).unwrap(), "4");
+    }
+
+    #[test]
+    fn rejects_backwards_ranges() {
+        match solve(&mut "2-4,8-6\n".as_bytes(), 1) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (1, 5)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn groups_of_any_size() {
+        let line = Line {
+            number: 1,
+            text: "2-4,6-8,3-5,6-8".to_owned(),
+        };
+        let group = Group::from_line(&line).unwrap();
+        assert_eq!(group.members.len(), 4);
+        assert_eq!(group.containments().collect::<Vec<_>>(), vec![(3, 1)]);
+        assert_eq!(group.overlaps().collect::<Vec<_>>(), vec![(0, 2), (1, 3)]);
+
+        let input = "1-2,3-4,5-6\n1-9\n1-5,4-6,8-9\n";
+        assert_eq!(solve(&mut input.as_bytes(), 1).unwrap(), "0");
+        assert_eq!(solve(&mut input.as_bytes(), 2).unwrap(), "1");
+    }
+
+    #[test]
+    fn sweeps_coverage_depth() {
+        let runs = coverage(&[
+            Assignment::new(2, 4),
+            Assignment::new(3, 6),
+            Assignment::new(9, 9),
+            Assignment::new(4, 4),
+        ]);
+        assert_eq!(
+            runs,
+            vec![
+                (Assignment::new(2, 2), 1),
+                (Assignment::new(3, 3), 2),
+                (Assignment::new(4, 4), 3),
+                (Assignment::new(5, 6), 1),
+                (Assignment::new(7, 8), 0),
+                (Assignment::new(9, 9), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn coverage_reaches_the_last_section() {
+        let runs = coverage(&[Assignment::new(0, usize::MAX), Assignment::new(3, 4)]);
+        assert_eq!(
+            runs,
+            vec![
+                (Assignment::new(0, 2), 1),
+                (Assignment::new(3, 4), 2),
+                (Assignment::new(5, usize::MAX), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn sizes_saturate_at_the_last_section() {
+        let everything = Assignment::new(0, usize::MAX);
+        assert_eq!(everything.size(), usize::MAX);
+        assert_eq!(IntervalSet::from(everything).len(), usize::MAX);
+        assert_eq!(Assignment::new(1, usize::MAX).size(), usize::MAX);
+        assert_eq!(label(&everything), format!("0-{}", usize::MAX));
+        assert_eq!(
+            label(&Assignment::new(usize::MAX, usize::MAX)),
+            usize::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn normalises_touching_ranges() {
+        let set: IntervalSet = [
+            Assignment::new(5, 7),
+            Assignment::new(2, 4),
+            Assignment::new(9, 9),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            set.ranges(),
+            &[Assignment::new(2, 7), Assignment::new(9, 9)]
+        );
+        assert_eq!(set.len(), 7);
+    }
+
+    proptest! {
+        #[test]
+        fn assignments_match_brute_force(a in assignments(), b in assignments()) {
+            let (a_sections, b_sections) = (sections(&[a]), sections(&[b]));
+            prop_assert_eq!(a.contains(&b), b_sections.is_subset(&a_sections));
+            prop_assert_eq!(a.overlaps(&b), !a_sections.is_disjoint(&b_sections));
+            let intersection: BTreeSet<usize> = a_sections.intersection(&b_sections).copied().collect();
+            prop_assert_eq!(sections(&a.intersection(&b).into_iter().collect::<Vec<_>>()), intersection);
+            let union: BTreeSet<usize> = a_sections.union(&b_sections).copied().collect();
+            prop_assert_eq!(sections(a.union(&b).ranges()), union);
+            let difference: BTreeSet<usize> = a_sections.difference(&b_sections).copied().collect();
+            prop_assert_eq!(sections(a.difference(&b).ranges()), difference);
+        }
+
+        #[test]
+        fn sweep_finds_every_overlap(ranges in prop::collection::vec(assignments(), 0..40)) {
+            let mut swept: Vec<(usize, usize)> = Vec::new();
+            for_each_overlap(&ranges, |i, j| swept.push((i.min(j), i.max(j))));
+            swept.sort_unstable();
+            let mut brute: Vec<(usize, usize)> = Vec::new();
+            for i in 0..ranges.len() {
+                for j in i + 1..ranges.len() {
+                    if ranges[i].overlaps(&ranges[j]) {
+                        brute.push((i, j));
+                    }
+                }
+            }
+            prop_assert_eq!(swept, brute);
+        }
+
+        #[test]
+        fn interval_sets_match_brute_force((a_ranges, a) in interval_sets(), (b_ranges, b) in interval_sets()) {
+            let (a_sections, b_sections) = (sections(&a_ranges), sections(&b_ranges));
+            prop_assert_eq!(sections(a.ranges()), a_sections.clone());
+            prop_assert_eq!(a.len(), a_sections.len());
+            for pair in a.ranges().windows(2) {
+                prop_assert!(pair[0].end + 1 < pair[1].start);
+            }
+            for section in 0..80 {
+                prop_assert_eq!(a.contains_section(section), a_sections.contains(&section));
+            }
+            let union: BTreeSet<usize> = a_sections.union(&b_sections).copied().collect();
+            prop_assert_eq!(sections(a.union(&b).ranges()), union);
+            let intersection: BTreeSet<usize> = a_sections.intersection(&b_sections).copied().collect();
+            prop_assert_eq!(sections(a.intersection(&b).ranges()), intersection);
+            let difference: BTreeSet<usize> = a_sections.difference(&b_sections).copied().collect();
+            prop_assert_eq!(sections(a.difference(&b).ranges()), difference);
+        }
     }
 }
@@ -1,11 +1,57 @@
+use crate::animate;
+use crate::parse::{self, Line, ParseError};
+use crate::trace;
 use regex::Regex;
+use serde_json::json;
 use std::fmt;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::str::FromStr;
 
-#[allow(dead_code)]
-enum Crane {
+/// How a crane carries a batch of crates from one stack to another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crane {
+    /// One crate per lift
     CrateMover9000,
+    /// The whole batch in one lift, keeping its order
     CrateMover9001,
+    /// At most this many crates per lift, each lift keeping its order
+    Limited(usize),
+    /// The whole batch in one lift, set down upside down. The stacks end up as with
+    /// the 9000, but in a single lift.
+    Reversing,
+    /// One crate per lift, except the top crate waits in the buffer until the rest
+    /// have been moved and then goes back on top
+    TwoArm,
+}
+
+impl Crane {
+    fn for_part(part: u8) -> Crane {
+        match part {
+            1 => Crane::CrateMover9000,
+            2 => Crane::CrateMover9001,
+            _ => panic!("Unimplemented part!"),
+        }
+    }
+}
+
+impl FromStr for Crane {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Crane, String> {
+        match s {
+            "9000" => Ok(Crane::CrateMover9000),
+            "9001" => Ok(Crane::CrateMover9001),
+            "reversing" => Ok(Crane::Reversing),
+            "two-arm" => Ok(Crane::TwoArm),
+            _ => match s.strip_prefix("limited:").map(str::parse) {
+                Some(Ok(k)) if k > 0 => Ok(Crane::Limited(k)),
+                _ => Err(format!(
+                    "expected `9000`, `9001`, `limited:K` with K > 0, `reversing` or `two-arm`, found `{}`",
+                    s
+                )),
+            },
+        }
+    }
 }
 
 struct Crates {
@@ -13,61 +59,266 @@ struct Crates {
     crane: Crane,
 }
 
-impl Crates {
-    fn from_lines(lines: &mut Vec<String>, crane: Crane) -> Self {
-        let num_columns: usize = lines
-            .pop()
-            .unwrap()
-            .split_ascii_whitespace()
-            .collect::<Vec<_>>()
-            .len();
-        let max_height = lines.len() * num_columns;
-        let mut stacks: Vec<Vec<char>> = Vec::with_capacity(num_columns);
-        for _ in 0..num_columns {
-            let v: Vec<char> = Vec::with_capacity(max_height);
-            stacks.push(v);
-        }
-        while let Some(line) = lines.pop() {
-            let row = line.chars().enumerate();
-            for (pos, char) in row {
-                match char {
-                    'A'..='Z' => {
-                        // 1 -> 0, 5 -> 1, 9 -> 2
-                        let idx = (pos - 1) / 4;
-                        stacks[idx].push(char);
-                    }
-                    _ => {}
+/// `move 1 from 2 to 1`, with stacks numbered from 1 as in the input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Move {
+    amount: usize,
+    from: usize,
+    onto: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.amount, self.from, self.onto
+        )
+    }
+}
+
+fn parse_move(re: &Regex, line: &Line) -> Result<Move, ParseError> {
+    let cap = re
+        .captures(&line.text)
+        .ok_or_else(|| line.error(1, "expected `move N from N to N`"))?;
+    Ok(Move {
+        amount: line.parse(cap.get(1).unwrap().as_str())?,
+        from: line.parse(cap.get(2).unwrap().as_str())?,
+        onto: line.parse(cap.get(3).unwrap().as_str())?,
+    })
+}
+
+/// The starting stacks, bottom crate first, and the numbered move lines,
+/// read from and written back out in exactly the puzzle's format
+#[derive(Debug, PartialEq, Eq)]
+struct Puzzle {
+    stacks: Vec<Vec<char>>,
+    moves: Vec<(usize, Move)>,
+}
+
+/// Stacks from a diagram whose last line numbers them `1` to `n`
+fn read_stacks(diagram: &[Line]) -> Result<Vec<Vec<char>>, ParseError> {
+    let Some((footer, rows)) = diagram.split_last() else {
+        return Err(ParseError::at_line(1, "expected a diagram of crate stacks"));
+    };
+    let mut count = 0;
+    for label in footer.text.split_ascii_whitespace() {
+        count += 1;
+        if label != count.to_string() {
+            return Err(footer.error_at(label, format!("expected stack {}", count)));
+        }
+    }
+
+    let mut stacks: Vec<Vec<char>> = vec![Vec::with_capacity(rows.len()); count];
+    for (height, row) in rows.iter().rev().enumerate() {
+        let cells: Vec<char> = row.text.chars().collect();
+        for (idx, cell) in cells.chunks(4).enumerate() {
+            let column = idx * 4 + 1;
+            let c = match cell {
+                ['[', c, ']', ..] => *c,
+                cell if cell.iter().all(|c| *c == ' ') => continue,
+                _ => return Err(row.error(column, "expected `[X]` or blanks")),
+            };
+            if idx >= count {
+                return Err(row.error(column, format!("crate {} is beyond the last stack", c)));
+            }
+            if stacks[idx].len() != height {
+                return Err(row.error(column, format!("crate {} has nothing under it", c)));
+            }
+            stacks[idx].push(c);
+        }
+    }
+    Ok(stacks)
+}
+
+impl Puzzle {
+    fn read(input: &mut dyn BufRead) -> Result<Puzzle, ParseError> {
+        let (puzzle, malformed) = Puzzle::read_skipping(input)?;
+        if malformed.is_empty() {
+            Ok(puzzle)
+        } else {
+            Err(ParseError::combine(malformed))
+        }
+    }
+
+    /// Like `read`, but leaves out move lines that don't parse, handing back their
+    /// errors alongside the puzzle
+    fn read_skipping(input: &mut dyn BufRead) -> Result<(Puzzle, Vec<ParseError>), ParseError> {
+        let mut sections = parse::paragraphs(input);
+        let diagram = sections.next().transpose()?.unwrap_or_default();
+        let stacks = read_stacks(&diagram)?;
+        // move 1 from 2 to 1
+        let re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
+        let mut moves: Vec<(usize, Move)> = Vec::new();
+        let mut malformed: Vec<ParseError> = Vec::new();
+        for section in sections {
+            for line in section? {
+                match parse_move(&re, &line) {
+                    Ok(step) => moves.push((line.number, step)),
+                    Err(e) => malformed.push(e),
                 }
             }
         }
-        Self { stacks, crane }
+        Ok((Puzzle { stacks, moves }, malformed))
+    }
+}
+
+impl fmt::Display for Puzzle {
+    /// The exact input format: rows padded to the full width, stack numbers
+    /// centred under their crates, a blank line, then the moves
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{}]", c),
+                    None => "   ".to_owned(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let labels: Vec<String> = (1..=self.stacks.len())
+            .map(|n| format!("{:^3}", n))
+            .collect();
+        writeln!(f, "{}", labels.join(" "))?;
+        writeln!(f)?;
+        for (_, step) in &self.moves {
+            writeln!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+impl Crates {
+    /// Why `step` can't be made from the current stacks, if it can't
+    fn check(&self, step: &Move) -> Result<(), String> {
+        let count = self.stacks.len();
+        for (role, stack) in [("from", step.from), ("to", step.onto)] {
+            if stack == 0 || stack > count {
+                return Err(format!(
+                    "`{}` names {} {}, but there are only stacks 1 to {}",
+                    step, role, stack, count
+                ));
+            }
+        }
+        let held = self.stacks[step.from - 1].len();
+        if step.amount > held {
+            return Err(format!(
+                "`{}` needs {} crates, but stack {} only holds {}",
+                step, step.amount, step.from, held
+            ));
+        }
+        Ok(())
+    }
+
+    /// `1: ZN, 2: MCD, 3: P`, bottom crate first
+    fn state(&self) -> String {
+        self.stacks
+            .iter()
+            .enumerate()
+            .map(|(i, stack)| format!("{}: {}", i + 1, stack.iter().collect::<String>()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Make a move that has already passed `check`, returning how many lifts it took
+    fn move_items(&mut self, amount: usize, from: usize, onto: usize) -> usize {
+        self.move_with(self.crane, amount, from, onto)
     }
 
-    fn move_items(&mut self, amount: usize, from: usize, onto: usize) {
-        match self.crane {
+    fn move_with(&mut self, crane: Crane, amount: usize, from: usize, onto: usize) -> usize {
+        let (from, onto) = (from - 1, onto - 1);
+        match crane {
             Crane::CrateMover9000 => {
                 for _ in 0..amount {
-                    let c = self.stacks[from - 1].pop().expect(&format!(
-                        "Invalid! move {} from {} to {}",
-                        amount, from, onto
-                    ));
-                    self.stacks[onto - 1].push(c);
+                    let c = self.stacks[from]
+                        .pop()
+                        .expect("Moves are checked before they're made");
+                    self.stacks[onto].push(c);
                 }
+                amount
             }
-            Crane::CrateMover9001 => {
-                let new_size = self.stacks[from - 1].len() - amount;
-                let moved = self.stacks[from - 1].split_off(new_size);
-                self.stacks[onto - 1].extend(moved);
+            Crane::CrateMover9001 => self.lift(amount, from, onto, false),
+            Crane::Limited(capacity) => {
+                let mut lifts = 0;
+                let mut left = amount;
+                while left > 0 {
+                    let batch = left.min(capacity);
+                    lifts += self.lift(batch, from, onto, false);
+                    left -= batch;
+                }
+                lifts
+            }
+            Crane::Reversing => self.lift(amount, from, onto, true),
+            Crane::TwoArm => {
+                if amount == 0 {
+                    return 0;
+                }
+                let parked = self.stacks[from]
+                    .pop()
+                    .expect("Moves are checked before they're made");
+                for _ in 1..amount {
+                    self.lift(1, from, onto, false);
+                }
+                self.stacks[onto].push(parked);
+                amount
             }
         }
     }
 
-    fn stack_tops(&self) -> Vec<char> {
-        let mut tops: Vec<char> = Vec::with_capacity(self.stacks.len());
-        for stack in &self.stacks {
-            tops.push(stack[stack.len() - 1]);
+    /// The moves, and the cranes to make them with, that put the stacks back the way
+    /// they were before `crane` made `step`
+    fn inverse(crane: Crane, step: &Move) -> Vec<(Crane, Move)> {
+        let back = |amount| Move {
+            amount,
+            from: step.onto,
+            onto: step.from,
+        };
+        match crane {
+            // Lifting the last batch back first, then the one before it...
+            Crane::Limited(capacity) if step.amount > capacity => {
+                let mut batches = vec![capacity; step.amount / capacity];
+                if !step.amount.is_multiple_of(capacity) {
+                    batches.push(step.amount % capacity);
+                }
+                batches
+                    .into_iter()
+                    .rev()
+                    .map(|batch| (Crane::CrateMover9001, back(batch)))
+                    .collect()
+            }
+            // ...while every other model undoes itself when run backwards
+            crane => vec![(crane, back(step.amount))],
+        }
+    }
+
+    /// Carry the top `amount` crates over in one go, returning the lifts that took
+    fn lift(&mut self, amount: usize, from: usize, onto: usize, upside_down: bool) -> usize {
+        let new_size = self.stacks[from].len() - amount;
+        let mut moved = self.stacks[from].split_off(new_size);
+        if upside_down {
+            moved.reverse();
         }
-        tops
+        self.stacks[onto].extend(moved);
+        usize::from(amount > 0)
+    }
+
+    fn record(&self, event: &str) {
+        if trace::enabled() {
+            let stacks: Vec<String> = self.stacks.iter().map(|s| s.iter().collect()).collect();
+            trace::record(event, &json!({ "stacks": stacks }));
+        }
+    }
+
+    /// The top crate of every stack, with a space standing in for an empty stack so
+    /// that each keeps its place in the answer
+    fn stack_tops(&self) -> Vec<char> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.last().copied().unwrap_or(' '))
+            .collect()
     }
 }
 
@@ -85,7 +336,7 @@ impl fmt::Display for Crates {
                     write!(f, "    ")?;
                 }
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
             height -= 1;
         }
         for idx in 0..self.stacks.len() {
@@ -95,40 +346,428 @@ impl fmt::Display for Crates {
     }
 }
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = std::fs::File::open(filename).expect("Failed to open file");
-    let mut lines = BufReader::new(f).lines();
-    let mut first_part = Vec::with_capacity(16);
-    loop {
-        if let Some(Ok(lin)) = lines.next() {
-            if lin.len() == 0 {
-                break;
-            } else {
-                first_part.push(lin);
+/// Top crates after every move. Bad moves are an error, unless `keep_going`,
+/// in which case each is reported and skipped.
+fn rearrange(
+    input: &mut dyn BufRead,
+    crane: Crane,
+    keep_going: bool,
+) -> Result<String, ParseError> {
+    let puzzle = if keep_going {
+        let (puzzle, malformed) = Puzzle::read_skipping(input)?;
+        for error in malformed {
+            log::warn!("Skipping {}", error);
+        }
+        puzzle
+    } else {
+        Puzzle::read(input)?
+    };
+    let mut crates = Crates {
+        stacks: puzzle.stacks,
+        crane,
+    };
+    log::debug!("Begining crate setup:\n{}", crates);
+    animate::frame("Begining crate setup", &crates);
+    crates.record("setup");
+    let mut lifts = 0;
+    for (line, step) in &puzzle.moves {
+        if let Err(message) = crates.check(step) {
+            let error = ParseError::at_line(
+                *line,
+                format!("{} (stacks were {})", message, crates.state()),
+            );
+            if !keep_going {
+                return Err(error);
             }
+            log::warn!("Skipping {}", error);
+            continue;
         }
+        lifts += crates.move_items(step.amount, step.from, step.onto);
+        let event = step.to_string();
+        animate::frame(&event, &crates);
+        crates.record(&event);
     }
-    let crane = match part {
-        1 => Crane::CrateMover9000,
-        2 => Crane::CrateMover9001,
-        _ => panic!("Unimplemented part!"),
-    };
-    let mut crates = Crates::from_lines(&mut first_part, crane);
-    println!("Begining crate setup:\n{}", crates);
-    // move 1 from 2 to 1
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-    loop {
-        if let Some(Ok(text)) = lines.next() {
-            for cap in re.captures_iter(&text) {
-                let amount = &cap[1].parse::<usize>().unwrap();
-                let from = &cap[2].parse::<usize>().unwrap();
-                let onto = &cap[3].parse::<usize>().unwrap();
-                crates.move_items(*amount, *from, *onto);
+    log::debug!("After crate moves:\n{}", crates);
+    log::info!("The {:?} needed {} lifts", crates.crane, lifts);
+    Ok(crates.stack_tops().iter().collect())
+}
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    rearrange(input, Crane::for_part(part), false)
+}
+
+/// Steps through a puzzle's moves, keeping the inverse of each move made so that
+/// it can be undone without keeping every earlier state
+struct Debugger {
+    crates: Crates,
+    moves: Vec<(usize, Move)>,
+    undo: Vec<Vec<(Crane, Move)>>,
+    /// How many of the moves after the current one were undone, and so can be redone
+    redoable: usize,
+}
+
+impl Debugger {
+    fn new(puzzle: Puzzle, crane: Crane) -> Debugger {
+        Debugger {
+            crates: Crates {
+                stacks: puzzle.stacks,
+                crane,
+            },
+            moves: puzzle.moves,
+            undo: Vec::new(),
+            redoable: 0,
+        }
+    }
+
+    /// How many moves have been made
+    fn position(&self) -> usize {
+        self.undo.len()
+    }
+
+    fn forward(&mut self) -> Result<(), String> {
+        let Some((line, step)) = self.moves.get(self.position()) else {
+            return Err("No moves left".to_owned());
+        };
+        self.crates
+            .check(step)
+            .map_err(|message| format!("line {}: {}", line, message))?;
+        self.crates.move_items(step.amount, step.from, step.onto);
+        self.undo.push(Crates::inverse(self.crates.crane, step));
+        self.redoable = self.redoable.saturating_sub(1);
+        Ok(())
+    }
+
+    fn back(&mut self) -> Result<(), String> {
+        let inverse = self.undo.pop().ok_or("Nothing to undo")?;
+        for (crane, step) in inverse {
+            self.crates
+                .move_with(crane, step.amount, step.from, step.onto);
+        }
+        self.redoable += 1;
+        Ok(())
+    }
+
+    fn jump(&mut self, target: usize) -> Result<(), String> {
+        if target > self.moves.len() {
+            return Err(format!("There are only {} moves", self.moves.len()));
+        }
+        while self.position() > target {
+            self.back()?;
+        }
+        while self.position() < target {
+            self.forward()?;
+        }
+        Ok(())
+    }
+
+    fn show(&self) {
+        match self.position() {
+            0 => println!("Before any moves:"),
+            n => {
+                let (line, step) = &self.moves[n - 1];
+                println!(
+                    "After move {} of {} (line {}): {}",
+                    n,
+                    self.moves.len(),
+                    line,
+                    step
+                );
             }
-        } else {
+        }
+        println!("{}", self.crates);
+    }
+
+    /// Carry out one command, returning whether to keep going
+    fn command(&mut self, command: &str) -> Result<bool, String> {
+        let mut words = command.split_ascii_whitespace();
+        let number = |word: Option<&str>| -> Result<usize, String> {
+            word.and_then(|w| w.parse().ok())
+                .ok_or_else(|| format!("`{}` needs a number", command))
+        };
+        match words.next() {
+            None | Some("n") => self.forward()?,
+            Some("u") => self.back()?,
+            Some("r") if self.redoable == 0 => return Err("Nothing to redo".to_owned()),
+            Some("r") => self.forward()?,
+            Some("j") => self.jump(number(words.next())?)?,
+            Some("s") => {
+                let n = number(words.next())?;
+                let stack = n
+                    .checked_sub(1)
+                    .and_then(|i| self.crates.stacks.get(i))
+                    .ok_or_else(|| format!("There is no stack {}", n))?;
+                println!("Stack {}: {}", n, stack.iter().collect::<String>());
+                return Ok(true);
+            }
+            Some("q") => return Ok(false),
+            Some(other) => return Err(format!("Unrecognised command `{}`", other)),
+        }
+        self.show();
+        Ok(true)
+    }
+}
+
+/// Read debugger commands from stdin until `q` or the input runs out
+fn debug(puzzle: Puzzle, crane: Crane) -> Result<(), ParseError> {
+    let mut debugger = Debugger::new(puzzle, crane);
+    let interactive = io::stdin().is_terminal();
+    debugger.show();
+    loop {
+        if interactive {
+            print!(
+                "[enter] next, [u]ndo, [r]edo, [j N] jump to move N, [s N] show stack N, [q]uit > "
+            );
+            io::stdout().flush()?;
+        }
+        let mut command = String::new();
+        if io::stdin().lock().read_line(&mut command)? == 0 {
             break;
         }
+        match debugger.command(command.trim()) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(message) => println!("{}", message),
+        }
+    }
+    Ok(())
+}
+
+#[derive(clap::Args, Debug)]
+#[group(id = "day5")]
+#[command(next_help_heading = "Day 5")]
+pub struct Options {
+    /// Print the puzzle back out in its input format instead of solving it
+    #[arg(long)]
+    rewrite: bool,
+
+    /// Report and skip moves the stacks can't support instead of stopping at the first
+    #[arg(long)]
+    keep_going: bool,
+
+    /// The crane to use: `9000`, `9001`, `limited:K`, `reversing` or `two-arm`
+    /// [default: 9000 for part 1, 9001 for part 2]
+    #[arg(long, value_name = "MODEL")]
+    crane: Option<Crane>,
+
+    /// Step through the moves, reading commands from stdin: enter for the next move,
+    /// `u` to undo, `r` to redo, `j N` to jump to move N, `s N` to show stack N
+    #[arg(long, conflicts_with = "rewrite")]
+    step: bool,
+}
+
+pub fn day_main(filename: &str, part: u8, options: &Options) -> Result<(), ParseError> {
+    if options.rewrite {
+        print!("{}", Puzzle::read(&mut parse::open(filename)?)?);
+        return Ok(());
+    }
+    let crane = options.crane.unwrap_or_else(|| Crane::for_part(part));
+    if options.step {
+        return debug(Puzzle::read(&mut parse::open(filename)?)?, crane);
+    }
+    let tops = rearrange(&mut parse::open(filename)?, crane, options.keep_going)?;
+    println!("Tops: {:?}", tops);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+
+    #[test]
+    fn round_trips_the_input() {
+        let puzzle = Puzzle::read(&mut EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(puzzle.stacks[1], vec!['M', 'C', 'D']);
+        assert_eq!(
+            puzzle.moves[0],
+            (
+                6,
+                Move {
+                    amount: 1,
+                    from: 2,
+                    onto: 1
+                }
+            )
+        );
+        let written = puzzle.to_string();
+        assert_eq!(written, EXAMPLE);
+        assert_eq!(Puzzle::read(&mut written.as_bytes()).unwrap(), puzzle);
+    }
+
+    #[test]
+    fn round_trips_more_than_nine_stacks() {
+        let mut stacks: Vec<Vec<char>> = (0..12).map(|i| vec![(b'A' + i as u8) as char]).collect();
+        stacks[10].push('Z');
+        stacks[3].clear();
+        let puzzle = Puzzle {
+            stacks,
+            moves: vec![(
+                5,
+                Move {
+                    amount: 2,
+                    from: 11,
+                    onto: 12,
+                },
+            )],
+        };
+        let written = puzzle.to_string();
+        assert!(written.contains(" 9  10  11  12 \n\nmove 2 from 11 to 12\n"));
+        assert_eq!(Puzzle::read(&mut written.as_bytes()).unwrap(), puzzle);
+        assert_eq!(solve(&mut written.as_bytes(), 2).unwrap(), "ABC EFGHIJ Z");
+    }
+
+    #[test]
+    fn rejects_floating_crates() {
+        let input = "[A]    \n    [B]\n 1   2 \n";
+        match Puzzle::read(&mut input.as_bytes()) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (1, 1)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_every_malformed_move() {
+        let input =
+            "[A]    \n[B] [C]\n 1   2 \n\nmove x from 1 to 2\nmove 1 from 1 to 2\nshift 1\n";
+        match Puzzle::read(&mut input.as_bytes()) {
+            Err(ParseError::Many(errors)) => {
+                let lines: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                assert_eq!(lines.len(), 2);
+                assert!(lines[0].starts_with("line 5, "), "{}", lines[0]);
+                assert!(lines[1].starts_with("line 7, "), "{}", lines[1]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            rearrange(&mut input.as_bytes(), Crane::CrateMover9000, true).unwrap(),
+            "BA"
+        );
+    }
+
+    #[test]
+    fn reports_bad_moves_with_the_stacks() {
+        let input = "[A]    \n[B] [C]\n 1   2 \n\nmove 3 from 1 to 2\nmove 1 from 3 to 1\nmove 1 from 2 to 1\n";
+        match solve(&mut input.as_bytes(), 2) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "line 5, column 1: `move 3 from 1 to 2` needs 3 crates, but stack 1 only holds 2 (stacks were 1: BA, 2: C)"
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        // Carrying on past both bad moves, which leaves stack 2 empty
+        assert_eq!(
+            rearrange(&mut input.as_bytes(), Crane::CrateMover9000, true).unwrap(),
+            "C "
+        );
+        let crates = Crates {
+            stacks: vec![vec!['A']],
+            crane: Crane::CrateMover9000,
+        };
+        let step = Move {
+            amount: 1,
+            from: 0,
+            onto: 1,
+        };
+        assert!(crates.check(&step).unwrap_err().contains("from 0"));
+    }
+
+    /// Stacks after moving all four crates of `ABCD` (D on top) onto an empty stack
+    fn moved_with(crane: Crane) -> (String, usize) {
+        let mut crates = Crates {
+            stacks: vec![vec!['A', 'B', 'C', 'D'], vec![]],
+            crane,
+        };
+        let lifts = crates.move_items(4, 1, 2);
+        (crates.stacks[1].iter().collect(), lifts)
+    }
+
+    #[test]
+    fn crane_models() {
+        assert_eq!(moved_with(Crane::CrateMover9000), ("DCBA".to_owned(), 4));
+        assert_eq!(moved_with(Crane::CrateMover9001), ("ABCD".to_owned(), 1));
+        assert_eq!(moved_with(Crane::Limited(3)), ("BCDA".to_owned(), 2));
+        assert_eq!(
+            moved_with(Crane::Limited(1)),
+            moved_with(Crane::CrateMover9000)
+        );
+        assert_eq!(moved_with(Crane::Reversing), ("DCBA".to_owned(), 1));
+        assert_eq!(moved_with(Crane::TwoArm), ("CBAD".to_owned(), 4));
+    }
+
+    #[test]
+    fn two_arm_moving_nothing_leaves_the_stacks_alone() {
+        let before = vec![vec!['A', 'B'], vec!['X']];
+        let mut crates = Crates {
+            stacks: before.clone(),
+            crane: Crane::TwoArm,
+        };
+        assert_eq!(crates.move_items(0, 1, 2), 0);
+        assert_eq!(crates.stacks, before);
+    }
+
+    #[test]
+    fn parses_crane_models() {
+        assert_eq!("limited:2".parse(), Ok(Crane::Limited(2)));
+        assert_eq!("two-arm".parse(), Ok(Crane::TwoArm));
+        assert!("limited:0".parse::<Crane>().is_err());
+        assert!("9002".parse::<Crane>().is_err());
+        assert_eq!(
+            rearrange(&mut EXAMPLE.as_bytes(), Crane::Limited(2), false).unwrap(),
+            "MCZ"
+        );
+    }
+
+    #[test]
+    fn inverse_moves_undo_every_crane() {
+        let cranes = [
+            Crane::CrateMover9000,
+            Crane::CrateMover9001,
+            Crane::Limited(3),
+            Crane::Limited(2),
+            Crane::Reversing,
+            Crane::TwoArm,
+        ];
+        for crane in cranes {
+            for amount in 0..=5 {
+                let before = vec![vec!['A', 'B', 'C', 'D', 'E'], vec!['X']];
+                let mut crates = Crates {
+                    stacks: before.clone(),
+                    crane,
+                };
+                let step = Move {
+                    amount,
+                    from: 1,
+                    onto: 2,
+                };
+                crates.move_items(step.amount, step.from, step.onto);
+                for (crane, back) in Crates::inverse(crane, &step) {
+                    crates.move_with(crane, back.amount, back.from, back.onto);
+                }
+                assert_eq!(crates.stacks, before, "{:?} moving {}", crane, amount);
+            }
+        }
+    }
+
+    #[test]
+    fn debugger_undoes_redoes_and_jumps() {
+        let puzzle = Puzzle::read(&mut EXAMPLE.as_bytes()).unwrap();
+        let mut debugger = Debugger::new(puzzle, Crane::CrateMover9001);
+        let tops = |d: &Debugger| d.crates.stack_tops().iter().collect::<String>();
+        assert_eq!(debugger.command("r"), Err("Nothing to redo".to_owned()));
+        debugger.command("j 4").unwrap();
+        assert_eq!(tops(&debugger), "MCD");
+        debugger.command("u").unwrap();
+        debugger.command("u").unwrap();
+        assert_eq!(debugger.position(), 2);
+        debugger.command("r").unwrap();
+        assert_eq!(debugger.redoable, 1);
+        debugger.command("j 0").unwrap();
+        assert_eq!(tops(&debugger), "NDP");
+        debugger.command("").unwrap();
+        assert_eq!(tops(&debugger), "DCP");
+        assert!(debugger.command("s 9").is_err());
+        assert!(debugger.command("j 5").is_err());
+        assert_eq!(debugger.command("q"), Ok(false));
     }
-    println!("After crate moves:\n{}", crates);
-    println!("Tops: {:?}", crates.stack_tops().iter().collect::<String>());
 }
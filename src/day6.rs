@@ -1,26 +1,40 @@
+use crate::parse::{self, ParseError};
 use std::collections::{HashSet, VecDeque};
+use std::io::BufRead;
+
+const NO_MARKER: &str = "No marker found!";
 
 enum Marker {
     StartOfPacket = 4,
     StartOfMessage = 14,
 }
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = std::fs::read_to_string(filename).expect("Failed to read file");
+/// Where the first marker ends, if the stream has one at all
+fn marker_start(input: &mut dyn BufRead, part: u8) -> Result<Option<usize>, ParseError> {
+    let mut f = String::new();
+    input.read_to_string(&mut f)?;
     let mut stream = f.chars();
     let marker = match part {
         1 => Marker::StartOfPacket,
         2 => Marker::StartOfMessage,
         _ => panic!("Unimplemented part!"),
     };
-    match find_marker_start(&mut stream, marker) {
-        Some(marker_start) => {
-            println!("Marker starts at index {}", marker_start);
-        }
-        None => {
-            println!("No marker found!");
-        }
+    Ok(find_marker_start(&mut stream, marker))
+}
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    Ok(match marker_start(input, part)? {
+        Some(marker_start) => marker_start.to_string(),
+        None => NO_MARKER.to_owned(),
+    })
+}
+
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    match marker_start(&mut parse::open(filename)?, part)? {
+        Some(marker_start) => println!("Marker starts at index {}", marker_start),
+        None => println!("{}", NO_MARKER),
     }
+    Ok(())
 }
 
 fn find_marker_start(
@@ -30,8 +44,7 @@ fn find_marker_start(
     let marker_len = marker_type as usize;
     let mut buffer: VecDeque<char> = VecDeque::with_capacity(marker_len);
     let mut check: HashSet<&char>; // = HashSet::with_capacity(marker_len);
-    let mut e = stream.enumerate();
-    while let Some((idx, chr)) = e.next() {
+    for (idx, chr) in stream.enumerate() {
         if buffer.len() == marker_len {
             buffer.pop_front();
         }
@@ -167,4 +180,11 @@ mod tests {
             None
         );
     }
+    #[test]
+    fn no_marker_is_an_answer() {
+        assert_eq!(
+            solve(&mut "abcabc".as_bytes(), 1).unwrap(),
+            "No marker found!"
+        );
+    }
 }
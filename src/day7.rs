@@ -1,6 +1,7 @@
 #![allow(unused_variables)]
+use crate::parse::{self, Line, ParseError};
 use std::collections::{HashMap, HashSet};
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 const PART_ONE_SIZE_LIMIT: u32 = 100000;
 const TOTAL_FS_SIZE: u32 = 70000000;
@@ -11,9 +12,7 @@ struct FSObject {
     is_dir: bool,
     size: u32,
 }
-pub fn day_main(filename: &str, part: u8) {
-    let f = std::fs::File::open(filename).expect("Unable to read file");
-    let lines = BufReader::new(f).lines();
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
     let mut paths: HashMap<String, FSObject> = HashMap::with_capacity(256);
     paths.insert(
         "/".to_owned(),
@@ -23,45 +22,57 @@ pub fn day_main(filename: &str, part: u8) {
         },
     );
     let mut cwd: Vec<String> = Vec::with_capacity(16);
-    for line_ in lines {
-        if let Ok(line) = line_ {
-            interpret_line(&line, &mut paths, &mut cwd);
-        }
+    for line in parse::lines(input) {
+        interpret_line(&line?, &mut paths, &mut cwd)?;
     }
 
     if part == 1 {
-        let mut combined_size: u32 = 0;
+        let mut combined_size: u64 = 0;
         for (dirname, fsobj) in &paths {
             if fsobj.is_dir && fsobj.size <= PART_ONE_SIZE_LIMIT {
-                combined_size += fsobj.size;
+                combined_size += u64::from(fsobj.size);
             }
         }
-        println!("Combined size: {}", combined_size);
+        Ok(combined_size.to_string())
     } else {
         let root_size = paths.get("/").expect("We're missing root?").size;
-        let need_to_free = NEEDED_FREE_SPACE - (TOTAL_FS_SIZE - root_size);
+        let free = TOTAL_FS_SIZE.saturating_sub(root_size);
+        // nothing needs freeing if there's already room, so the smallest directory will do
+        let need_to_free = NEEDED_FREE_SPACE.saturating_sub(free);
         let mut big_enough: Vec<(&String, &FSObject)> = paths
             .iter()
-            .filter(|(&ref k, &ref v)| v.is_dir && v.size >= need_to_free)
+            .filter(|(k, v)| v.is_dir && v.size >= need_to_free)
             .collect();
-        big_enough.sort_by(|a, b| a.1.size.cmp(&b.1.size));
-        println!(
-            "Smallest dir that's big enough: {}, size: {}",
-            big_enough[0].0, big_enough[0].1.size
-        );
+        big_enough.sort_by_key(|(_, v)| v.size);
+        log::info!("Smallest dir that's big enough: {}", big_enough[0].0);
+        Ok(big_enough[0].1.size.to_string())
     }
 }
 
-fn interpret_line(line: &str, paths: &mut HashMap<String, FSObject>, cwd: &mut Vec<String>) {
-    let mut components = line.split_ascii_whitespace();
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    let size = solve(&mut parse::open(filename)?, part)?;
+    if part == 1 {
+        println!("Combined size: {}", size);
+    } else {
+        println!("Size of the smallest dir that's big enough: {}", size);
+    }
+    Ok(())
+}
+
+fn interpret_line(
+    line: &Line,
+    paths: &mut HashMap<String, FSObject>,
+    cwd: &mut Vec<String>,
+) -> Result<(), ParseError> {
+    let mut components = line.text.split_ascii_whitespace();
     if let Some(first) = components.next() {
-        let second = components.next().unwrap();
+        let second = line.next_field(&mut components, "name")?;
         match first {
             // command
             "$" => {
                 match second {
                     "cd" => {
-                        let newdir = components.next().unwrap();
+                        let newdir = line.next_field(&mut components, "directory")?;
                         match newdir {
                             "/" => {
                                 cwd.clear();
@@ -75,7 +86,10 @@ fn interpret_line(line: &str, paths: &mut HashMap<String, FSObject>, cwd: &mut V
                             }
                         }
                     }
-                    _ => {} // `ls` requires no action
+                    "ls" => {} // `ls` requires no action
+                    _ => {
+                        return Err(line.error_at(second, format!("unknown command `{}`", second)));
+                    }
                 }
             }
             // ls output
@@ -92,34 +106,37 @@ fn interpret_line(line: &str, paths: &mut HashMap<String, FSObject>, cwd: &mut V
                 );
             }
             _ => {
-                if let Ok(size) = first.parse::<u32>() {
-                    let mut this_path = cwd.as_slice().join("/");
-                    this_path.push('/');
-                    this_path.push_str(second);
-                    paths.insert(
-                        this_path,
-                        FSObject {
-                            is_dir: false,
-                            size,
-                        },
-                    );
-                    let mut to_update: HashSet<String> = HashSet::with_capacity(cwd.len());
-                    for i in 0..=cwd.len() {
-                        let mut dirname = cwd[..i].join("/");
-                        if dirname.is_empty() {
-                            dirname = "/".to_owned();
-                        }
-                        to_update.insert(dirname);
+                let size = line.parse::<u32>(first)?;
+                let mut this_path = cwd.as_slice().join("/");
+                this_path.push('/');
+                this_path.push_str(second);
+                paths.insert(
+                    this_path,
+                    FSObject {
+                        is_dir: false,
+                        size,
+                    },
+                );
+                let mut to_update: HashSet<String> = HashSet::with_capacity(cwd.len());
+                for i in 0..=cwd.len() {
+                    let mut dirname = cwd[..i].join("/");
+                    if dirname.is_empty() {
+                        dirname = "/".to_owned();
                     }
-                    for dirname in to_update {
-                        if let Some(d) = paths.get_mut(&dirname) {
-                            d.size += size;
-                        }
+                    to_update.insert(dirname);
+                }
+                for dirname in to_update {
+                    if let Some(d) = paths.get_mut(&dirname) {
+                        d.size = d.size.checked_add(size).ok_or_else(|| {
+                            line.error_at(
+                                first,
+                                format!("`{}` grows past {} bytes", dirname, u32::MAX),
+                            )
+                        })?;
                     }
-                } else {
-                    eprintln!("Unable to understand line '{} {}'", first, second);
                 }
             }
         }
     }
+    Ok(())
 }
@@ -1,5 +1,6 @@
+use crate::parse::{self, Line, ParseError};
 use std::collections::HashSet;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 type Row = Vec<u8>;
 type Matrix = Vec<Row>;
@@ -88,22 +89,35 @@ macro_rules! tallest_this_direction {
     };
 }
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = std::fs::File::open(filename).expect("Unable to open file");
-    let lines = BufReader::new(f).lines();
-    let matrix = create_matrix(lines);
-    // println!("{:?}", &matrix);
-    match part {
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
+    let matrix = create_matrix(parse::lines(input))?;
+    log::trace!("{:?}", &matrix);
+    let answer = match part {
         1 => part1_main(&matrix),
         2 => part2_main(&matrix),
         _ => panic!("part not implemented"),
+    };
+    Ok(answer.to_string())
+}
+
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    let answer = solve(&mut parse::open(filename)?, part)?;
+    if part == 1 {
+        println!("{}", answer);
+    } else {
+        println!("Greatest score: {}", answer);
     }
+    Ok(())
 }
 
-fn part2_main(matrix: &Vec<Vec<u8>>) {
+fn width(matrix: &Matrix) -> usize {
+    matrix.first().map_or(0, Vec::len)
+}
+
+fn part2_main(matrix: &Matrix) -> usize {
     let mut scores: Vec<Vec<usize>> = Vec::with_capacity(matrix.len());
     for _ in 0..matrix.len() {
-        scores.push(vec![1 as usize; matrix[0].len()]);
+        scores.push(vec![1; width(matrix)]);
     }
     part2_run(matrix, &mut scores);
     let mut greatest: usize = 0;
@@ -112,13 +126,13 @@ fn part2_main(matrix: &Vec<Vec<u8>>) {
             greatest = greatest.max(col);
         }
     }
-    println!("Greatest score: {}", greatest);
+    greatest
 }
 
-fn part2_run(matrix: &Vec<Vec<u8>>, scores: &mut Vec<Vec<usize>>) {
-    for (row, row_of_trees) in matrix.iter().enumerate() {
-        for col in 0..row_of_trees.len() {
-            scores[row][col] = Looking::East(matrix).visible(row, col)
+fn part2_run(matrix: &Matrix, scores: &mut [Vec<usize>]) {
+    for (row, row_scores) in scores.iter_mut().enumerate().take(matrix.len()) {
+        for (col, score) in row_scores.iter_mut().enumerate().take(matrix[row].len()) {
+            *score = Looking::East(matrix).visible(row, col)
                 * Looking::West(matrix).visible(row, col)
                 * Looking::South(matrix).visible(row, col)
                 * Looking::North(matrix).visible(row, col);
@@ -127,6 +141,7 @@ fn part2_run(matrix: &Vec<Vec<u8>>, scores: &mut Vec<Vec<usize>>) {
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod test {
     use super::*;
 
@@ -148,7 +163,6 @@ mod test {
         ];
         part2_run(&matrix, &mut scores);
 
-        println!("{:?}", scores);
         let expected: Vec<Vec<usize>> = vec![
             vec![0, 0, 0, 0, 0, 0, 0, 0],
             vec![0, 1, 8, 2, 12, 2, 1, 0],
@@ -158,9 +172,19 @@ mod test {
         ];
         assert_eq!(expected, scores);
     }
+
+    #[test]
+    fn rejects_ragged_forests() {
+        match solve(&mut "303\n25\n".as_bytes(), 1) {
+            Err(ParseError::Malformed { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(solve(&mut "".as_bytes(), 1).unwrap(), "0");
+        assert_eq!(solve(&mut "".as_bytes(), 2).unwrap(), "0");
+    }
 }
 
-fn part1_main(matrix: &Vec<Vec<u8>>) {
+fn part1_main(matrix: &Matrix) -> usize {
     let mut visible: HashSet<Coord> = HashSet::with_capacity(128);
     let mut current_tallest: CurrentTallest;
     for row in 0..matrix.len() {
@@ -173,7 +197,7 @@ fn part1_main(matrix: &Vec<Vec<u8>>) {
             tallest_this_direction!(matrix, visible, row, col, current_tallest)
         }
     }
-    for col in (0..matrix[0].len()).rev() {
+    for col in (0..width(matrix)).rev() {
         current_tallest = None;
         for row in 0..matrix.len() {
             tallest_this_direction!(matrix, visible, row, col, current_tallest)
@@ -183,39 +207,52 @@ fn part1_main(matrix: &Vec<Vec<u8>>) {
             tallest_this_direction!(matrix, visible, row, col, current_tallest)
         }
     }
-    println!("{:?}", visible.len());
+    visible.len()
 }
 
-fn check_visible(matrix: &Vec<Row>, coord: Coord, current_tallest: &CurrentTallest) -> Found {
+fn check_visible(matrix: &[Row], coord: Coord, current_tallest: &CurrentTallest) -> Found {
     let (row, col) = coord;
     if matrix[row][col] == TALLEST_POSSIBLE {
         return Found::Tallest(coord);
     }
     match *current_tallest {
-        None => {
-            return Found::Taller(matrix[row][col], coord);
-        }
-        Some(tall) => {
-            if matrix[row][col] > tall {
-                return Found::Taller(matrix[row][col], coord);
-            }
-        }
+        None => Found::Taller(matrix[row][col], coord),
+        Some(tall) if matrix[row][col] > tall => Found::Taller(matrix[row][col], coord),
+        Some(_) => Found::Nothing,
     }
-    Found::Nothing
 }
 
-fn create_matrix<E>(lines: impl Iterator<Item = Result<String, E>>) -> Vec<Row> {
-    let mut matrix: Vec<Row> = Vec::with_capacity(128);
-    for line_ in lines {
-        if let Ok(line) = line_ {
-            let mut row: Row = line
-                .chars()
-                .map(|c| c.to_string().parse::<u8>().unwrap())
-                .collect();
-            row.shrink_to_fit();
-            matrix.push(row);
+fn create_matrix(
+    lines: impl Iterator<Item = Result<Line, ParseError>>,
+) -> Result<Matrix, ParseError> {
+    let mut matrix: Matrix = Vec::with_capacity(128);
+    for line in lines {
+        let line = line?;
+        let mut row: Row = line
+            .text
+            .chars()
+            .enumerate()
+            .map(|(idx, c)| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| line.error(idx + 1, format!("expected a digit, found `{}`", c)))
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(first) = matrix.first() {
+            if row.len() != first.len() {
+                return Err(line.error(
+                    row.len().min(first.len()) + 1,
+                    format!(
+                        "expected {} trees in the row, found {}",
+                        first.len(),
+                        row.len()
+                    ),
+                ));
+            }
         }
+        row.shrink_to_fit();
+        matrix.push(row);
     }
     matrix.shrink_to_fit();
-    matrix
+    Ok(matrix)
 }
@@ -1,26 +1,56 @@
+use crate::animate;
+use crate::parse::{self, FromLine, Line, ParseError};
+use crate::trace;
+use serde_json::json;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
+use std::fmt;
+use std::io::BufRead;
 
 type Coords = (isize, isize);
-type LineBuffer = Lines<BufReader<File>>;
+type LineBuffer<'a> = parse::Lines<&'a mut dyn BufRead>;
 
-pub fn day_main(filename: &str, part: u8) {
-    let f = File::open(filename).expect("Unable to open file");
+// Size of the animation window, which follows the head around
+const VIEW_WIDTH: isize = 41;
+const VIEW_HEIGHT: isize = 21;
+
+/// The most steps the moves may add up to, far beyond any real puzzle but small
+/// enough that the head can't run off the edge of the coordinates
+const MAX_STEPS: usize = 1_000_000;
+
+pub fn solve(input: &mut dyn BufRead, part: u8) -> Result<String, ParseError> {
     let length: usize = match part {
         1 => 2,
         2 => 10,
         _ => panic!("Not implemented"),
     };
-    let lines = BufReader::new(f).lines();
-    let coords: HashSet<Coords> = State::new(length, lines).collect();
-    println!("Total spaces covered: {}", coords.len());
+    let lines = parse::lines(input);
+    let mut state = State::new(length, lines);
+    let mut coords: HashSet<Coords> = HashSet::new();
+    while let Some(tail) = state.next() {
+        coords.insert(tail?);
+        if animate::enabled() {
+            let title = format!("Tail has covered {} spaces", coords.len());
+            let view = RopeView {
+                rope: &state.rope,
+                visited: &coords,
+            };
+            animate::frame(&title, &view);
+        }
+    }
+    Ok(coords.len().to_string())
 }
 
-struct State {
+pub fn day_main(filename: &str, part: u8) -> Result<(), ParseError> {
+    let covered = solve(&mut parse::open(filename)?, part)?;
+    println!("Total spaces covered: {}", covered);
+    Ok(())
+}
+
+struct State<'a> {
     rope: Vec<Coords>,
-    moves: LineBuffer,
+    moves: LineBuffer<'a>,
     current: Option<Moves>,
+    steps: usize,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -32,13 +62,13 @@ enum Direction {
 }
 
 impl Direction {
-    fn from_str(s: &str) -> Self {
+    fn from_str(s: &str) -> Option<Self> {
         match s {
-            "U" => Direction::Up,
-            "D" => Direction::Down,
-            "L" => Direction::Left,
-            "R" => Direction::Right,
-            _ => panic!("Invalid direction received!"),
+            "U" => Some(Direction::Up),
+            "D" => Some(Direction::Down),
+            "L" => Some(Direction::Left),
+            "R" => Some(Direction::Right),
+            _ => None,
         }
     }
 }
@@ -71,12 +101,13 @@ impl Decrement for Option<Moves> {
     }
 }
 
-impl State {
-    fn new(length: usize, lines: LineBuffer) -> Self {
+impl<'a> State<'a> {
+    fn new(length: usize, lines: LineBuffer<'a>) -> Self {
         Self {
             rope: vec![(0, 0); length],
             moves: lines,
             current: None,
+            steps: 0,
         }
     }
 
@@ -98,33 +129,44 @@ impl State {
     }
 }
 
-fn read_line(line: &str) -> Moves {
-    let mut parts = line.split_ascii_whitespace();
-    let dir_str = parts.next().expect("Blank line received");
-    let count_str = parts.next().expect("Line missing move count");
-    let direction = Direction::from_str(dir_str);
-    let count: usize = count_str.parse().expect("Move count not an integer");
-    Moves { direction, count }
+impl FromLine for Moves {
+    fn from_line(line: &Line) -> Result<Self, ParseError> {
+        let mut parts = line.text.split_ascii_whitespace();
+        let dir_str = line.next_field(&mut parts, "direction")?;
+        let count_str = line.next_field(&mut parts, "move count")?;
+        let direction = line.convert(dir_str, Direction::from_str, "U, D, L or R")?;
+        let count: usize = line.parse(count_str)?;
+        Ok(Moves { direction, count })
+    }
 }
 
-impl Iterator for State {
-    type Item = Coords;
+impl Iterator for State<'_> {
+    type Item = Result<Coords, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current.is_none() {
-            let next_move = self.moves.next();
-            if let Some(Ok(this_move)) = next_move {
-                self.current = Some(read_line(&this_move));
-            } else if let Some(Err(e)) = next_move {
-                eprintln!("Unable to process line: {:?}", e);
-                return None;
-            } else {
-                return None;
+            let line = match self.moves.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            match Moves::from_line(&line) {
+                Ok(this_move) if this_move.count > MAX_STEPS - self.steps => {
+                    return Some(Err(line.error_at_end(format!(
+                        "the moves add up to more than {} steps",
+                        MAX_STEPS
+                    ))));
+                }
+                Ok(this_move) => {
+                    self.steps += this_move.count;
+                    self.current = Some(this_move);
+                }
+                Err(e) => return Some(Err(e)),
             }
         }
-        // println!("{:?}", self.rope);
-        // println!("instruction={:?}", self.current);
-        self.move_head(self.current.as_ref().unwrap().direction);
+        log::trace!("{:?}", self.rope);
+        log::trace!("instruction={:?}", self.current);
+        let direction = self.current.as_ref().unwrap().direction;
+        self.move_head(direction);
         self.current = self.current.decrement();
 
         for i in 1..self.rope.len() {
@@ -153,6 +195,89 @@ impl Iterator for State {
                 break;
             }
         }
-        Some(self.rope[self.rope.len() - 1])
+        if trace::enabled() {
+            trace::record(&format!("{:?}", direction), &json!({ "knots": self.rope }));
+        }
+        Some(Ok(self.rope[self.rope.len() - 1]))
+    }
+}
+
+/// The rope and the spaces its tail has covered, as seen in a window centred on the head
+struct RopeView<'a> {
+    rope: &'a [Coords],
+    visited: &'a HashSet<Coords>,
+}
+
+impl RopeView<'_> {
+    fn marker(&self, at: Coords) -> char {
+        match self.rope.iter().position(|&knot| knot == at) {
+            Some(0) => 'H',
+            Some(_) if self.rope.len() == 2 => 'T',
+            Some(i) => char::from_digit(i as u32, 36).unwrap_or('+'),
+            None if at == (0, 0) => 's',
+            None if self.visited.contains(&at) => '#',
+            None => '.',
+        }
+    }
+}
+
+impl fmt::Display for RopeView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (head_x, head_y) = self.rope[0];
+        let left = head_x - VIEW_WIDTH / 2;
+        let top = head_y + VIEW_HEIGHT / 2;
+        for y in ((top - VIEW_HEIGHT + 1)..=top).rev() {
+            for x in left..(left + VIEW_WIDTH) {
+                write!(f, "{}", self.marker((x, y)))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_the_total_steps() {
+        let input = format!("R 4\nU {}\n", MAX_STEPS - 3);
+        match solve(&mut input.as_bytes(), 1) {
+            Err(ParseError::Malformed { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn draws_the_rope_around_its_head() {
+        let rope = [(2, 1), (1, 1)];
+        let visited: HashSet<Coords> = [(0, 0), (1, 0), (1, 1)].into_iter().collect();
+        let view = RopeView {
+            rope: &rope,
+            visited: &visited,
+        };
+        let drawn = view.to_string();
+        let rows: Vec<&str> = drawn.lines().collect();
+        assert_eq!(rows.len(), VIEW_HEIGHT as usize);
+        assert!(rows.iter().all(|row| row.len() == VIEW_WIDTH as usize));
+        // The head sits in the middle of the view, with the start one row below
+        assert_eq!(rows[10], format!("{}TH{}", ".".repeat(19), ".".repeat(20)));
+        assert_eq!(rows[11], format!("{}s#{}", ".".repeat(18), ".".repeat(21)));
+        assert_eq!(rows[0], ".".repeat(41));
+    }
+
+    #[test]
+    fn numbers_longer_ropes() {
+        let rope = [(0, 0), (0, 0), (-1, 0), (-2, 0)];
+        let visited = HashSet::new();
+        let view = RopeView {
+            rope: &rope,
+            visited: &visited,
+        };
+        assert_eq!(view.marker((0, 0)), 'H');
+        assert_eq!(view.marker((-1, 0)), '2');
+        assert_eq!(view.marker((-2, 0)), '3');
+        assert_eq!(view.marker((1, 0)), '.');
     }
 }
@@ -0,0 +1,17 @@
+pub mod animate;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod logging;
+pub mod parse;
+pub mod serve;
+pub mod trace;
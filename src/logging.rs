@@ -0,0 +1,82 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Sends every log record to stderr so that stdout only ever carries answers
+struct StderrLogger;
+
+/// Problems are shown plainly; diagnostics also name the module they came from
+fn format(record: &Record) -> String {
+    match record.level() {
+        Level::Error | Level::Warn => format!("{}: {}", record.level(), record.args()),
+        _ => format!("{} [{}] {}", record.level(), record.target(), record.args()),
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", format(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+pub fn level_for(verbose: u8, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+pub fn init(verbose: u8, quiet: bool) {
+    log::set_logger(&LOGGER).expect("Logger already initialised");
+    log::set_max_level(level_for(verbose, quiet));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(level: Level, verbose: u8, quiet: bool) -> bool {
+        level <= level_for(verbose, quiet)
+    }
+
+    #[test]
+    fn filters_by_verbosity() {
+        assert!(shown(Level::Warn, 0, false));
+        assert!(!shown(Level::Info, 0, false));
+        assert!(shown(Level::Info, 1, false));
+        assert!(!shown(Level::Debug, 1, false));
+        assert!(shown(Level::Debug, 2, false));
+        assert!(shown(Level::Trace, 5, false));
+        assert!(shown(Level::Error, 0, true));
+        assert!(!shown(Level::Warn, 3, true));
+    }
+
+    #[test]
+    fn formats_lines() {
+        let line = |level| {
+            format(
+                &Record::builder()
+                    .level(level)
+                    .target("advent2022::day5")
+                    .args(format_args!("{} crates", 3))
+                    .build(),
+            )
+        };
+        assert_eq!(line(Level::Error), "ERROR: 3 crates");
+        assert_eq!(line(Level::Warn), "WARN: 3 crates");
+        assert_eq!(line(Level::Debug), "DEBUG [advent2022::day5] 3 crates");
+    }
+}
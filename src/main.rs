@@ -1,46 +1,185 @@
-pub mod day1;
-pub mod day10;
-pub mod day11;
-pub mod day12;
-pub mod day2;
-pub mod day3;
-pub mod day4;
-pub mod day5;
-pub mod day6;
-pub mod day7;
-pub mod day8;
-pub mod day9;
-
-use clap::Parser;
+use advent2022::{
+    animate, day1, day10, day11, day12, day2, day3, day4, day5, day6, day7, day8, day9, logging,
+    serve, trace,
+};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::process;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    day: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    #[arg(short, long)]
-    part: u8,
+    #[arg(short, long, required = true)]
+    day: Option<u8>,
+
+    #[arg(short, long, required = true)]
+    part: Option<u8>,
+
+    /// Show diagnostics on stderr; repeat for more detail
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only report errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Redraw each step of the simulation days in the terminal
+    #[arg(long)]
+    animate: bool,
+
+    /// Milliseconds to pause between animation frames
+    #[arg(long, default_value_t = 100, requires = "animate")]
+    frame_delay: u64,
+
+    /// Write one JSON record per simulation step to this file
+    #[arg(long, value_name = "FILE")]
+    trace: Option<String>,
+
+    #[command(flatten)]
+    day1: day1::Options,
+
+    #[command(flatten)]
+    day2: day2::Options,
+
+    #[command(flatten)]
+    day3: day3::Options,
+
+    #[command(flatten)]
+    day4: day4::Options,
+
+    #[command(flatten)]
+    day5: day5::Options,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Step through a trace written with --trace
+    Replay {
+        trace: String,
+
+        /// Report where this trace first diverges from another one
+        #[arg(long, value_name = "FILE")]
+        compare: Option<String>,
+    },
+    /// Answer puzzles over HTTP: POST the input to /solve/{day}/{part}
+    Serve {
+        #[arg(long, default_value_t = 8022)]
+        port: u16,
+
+        /// Seconds to wait on a client that stops sending or receiving
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timeout: u64,
+    },
+}
+
+/// Complain about a flag given for some other day than `day`, going by the
+/// `dayN` group each day's options belong to
+fn check_day_options(matches: &ArgMatches, day: u8) -> Result<(), String> {
+    let command = Args::command();
+    for group in command.get_groups() {
+        let Some(owner) = group
+            .get_id()
+            .as_str()
+            .strip_prefix("day")
+            .and_then(|n| n.parse::<u8>().ok())
+        else {
+            continue;
+        };
+        if owner == day {
+            continue;
+        }
+        for id in group.get_args() {
+            if matches.value_source(id.as_str()) != Some(ValueSource::CommandLine) {
+                continue;
+            }
+            let flag = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .and_then(|arg| arg.get_long())
+                .unwrap_or(id.as_str());
+            return Err(format!(
+                "the argument '--{}' only applies to day {}",
+                flag, owner
+            ));
+        }
+    }
+    Ok(())
 }
 
 fn main() {
-    let args = Args::parse();
-    let filename = format!("static/day{}-input.txt", args.day);
-    let day_main = {
-        match args.day {
-            1 => day1::day_main,
-            2 => day2::day_main,
-            3 => day3::day_main,
-            4 => day4::day_main,
-            5 => day5::day_main,
-            6 => day6::day_main,
-            7 => day7::day_main,
-            8 => day8::day_main,
-            9 => day9::day_main,
-            10 => day10::day_main,
-            11 => day11::day_main,
-            12 => day12::day_main,
-            _ => panic!("Not yet implemented!"),
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(args.verbose, args.quiet);
+    match &args.command {
+        Some(Command::Replay { trace, compare }) => {
+            let replayed = match compare {
+                Some(other) => trace::compare(trace, other),
+                None => trace::replay(trace),
+            };
+            if let Err(e) = replayed {
+                log::error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Serve { port, timeout }) => {
+            if let Err(e) = serve::serve(*port, Duration::from_secs(*timeout)) {
+                log::error!("Unable to serve on port {}: {}", port, e);
+                process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+
+    let (day, part) = (args.day.unwrap(), args.part.unwrap());
+    let checked = check_day_options(&matches, day).and_then(|()| match day {
+        3 => args.day3.check(part),
+        _ => Ok(()),
+    });
+    if let Err(message) = checked {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+    if args.animate {
+        animate::init(Duration::from_millis(args.frame_delay));
+    }
+    if let Some(path) = &args.trace {
+        if let Err(e) = trace::init(path, day, part) {
+            log::error!("{}: {}", path, e);
+            process::exit(1);
         }
+    }
+    let filename = format!("static/day{}-input.txt", day);
+    let solved = match day {
+        1 => day1::day_main(&filename, part, &args.day1),
+        2 => day2::day_main(&filename, part, &args.day2),
+        3 => day3::day_main(&filename, part, &args.day3),
+        4 => day4::day_main(&filename, part, &args.day4),
+        5 => day5::day_main(&filename, part, &args.day5),
+        6 => day6::day_main(&filename, part),
+        7 => day7::day_main(&filename, part),
+        8 => day8::day_main(&filename, part),
+        9 => day9::day_main(&filename, part),
+        10 => day10::day_main(&filename, part),
+        11 => day11::day_main(&filename, part),
+        12 => day12::day_main(&filename, part),
+        _ => panic!("Not yet implemented!"),
     };
-    day_main(&filename, args.part);
+    // Flush the trace first: a failed run is when it's most wanted
+    let traced = trace::finish();
+    if let Err(e) = solved {
+        log::error!("{}", e.in_file(&filename));
+        process::exit(1);
+    }
+    if let Err(e) = traced {
+        log::error!("Unable to write trace: {}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Malformed {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Many(Vec<ParseError>),
+    InFile {
+        path: String,
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// An error about a whole line, or about input with no line to point at when
+    /// `line` is 1
+    pub fn at_line(line: usize, message: impl Into<String>) -> ParseError {
+        ParseError::Malformed {
+            line,
+            column: 1,
+            message: message.into(),
+        }
+    }
+
+    /// A single error for everything in `errors`, which must not be empty
+    pub fn combine(mut errors: Vec<ParseError>) -> ParseError {
+        if errors.len() == 1 {
+            errors.remove(0)
+        } else {
+            ParseError::Many(errors)
+        }
+    }
+
+    /// Note which file the error came from, unless that's already known, including
+    /// for a collection of errors that each name their own file
+    pub fn in_file(self, path: &str) -> ParseError {
+        match self {
+            ParseError::InFile { .. } => self,
+            ParseError::Many(ref errors)
+                if errors
+                    .iter()
+                    .all(|e| matches!(e, ParseError::InFile { .. })) =>
+            {
+                self
+            }
+            error => ParseError::InFile {
+                path: path.to_owned(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{}", e),
+            ParseError::Malformed {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            ParseError::Many(errors) => {
+                write!(f, "{} problems found", errors.len())?;
+                for e in errors {
+                    write!(f, "\n  {}", e)?;
+                }
+                Ok(())
+            }
+            ParseError::InFile { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// A single line of input along with its 1-based line number
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub number: usize,
+    pub text: String,
+}
+
+impl Line {
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// The 1-based column at which `field` starts, provided it was sliced out of this line
+    pub fn column_of(&self, field: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let at = field.as_ptr() as usize;
+        if at >= start && at <= start + self.text.len() {
+            at - start + 1
+        } else {
+            1
+        }
+    }
+
+    pub fn error(&self, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError::Malformed {
+            line: self.number,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn error_at(&self, field: &str, message: impl Into<String>) -> ParseError {
+        self.error(self.column_of(field), message)
+    }
+
+    /// An error pointing just past the end of the line, for input that stops short
+    pub fn error_at_end(&self, message: impl Into<String>) -> ParseError {
+        self.error(self.text.len() + 1, message)
+    }
+
+    pub fn parse<T>(&self, field: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        field
+            .parse::<T>()
+            .map_err(|e| self.error_at(field, format!("invalid value `{}`: {}", field, e)))
+    }
+
+    /// Like `parse`, but for conversions that only report whether they succeeded
+    pub fn convert<T>(
+        &self,
+        field: &str,
+        convert: impl FnOnce(&str) -> Option<T>,
+        expected: &str,
+    ) -> Result<T, ParseError> {
+        convert(field).ok_or_else(|| {
+            self.error_at(field, format!("expected {}, found `{}`", expected, field))
+        })
+    }
+
+    pub fn split_once<'a>(
+        &self,
+        field: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        field
+            .split_once(delimiter)
+            .ok_or_else(|| self.error_at(field, format!("expected `{}` in `{}`", delimiter, field)))
+    }
+
+    /// Pull the next item out of `fields`, complaining about a missing `what` if there is none
+    pub fn next_field<'a>(
+        &self,
+        fields: &mut impl Iterator<Item = &'a str>,
+        what: &str,
+    ) -> Result<&'a str, ParseError> {
+        fields
+            .next()
+            .ok_or_else(|| self.error_at_end(format!("missing {}", what)))
+    }
+}
+
+/// Types that can be built from a single line of input
+pub trait FromLine: Sized {
+    fn from_line(line: &Line) -> Result<Self, ParseError>;
+}
+
+pub fn open(filename: &str) -> Result<BufReader<File>, ParseError> {
+    Ok(BufReader::new(File::open(filename)?))
+}
+
+/// Numbered lines of a reader, surfacing I/O errors rather than dropping them
+pub struct Lines<R> {
+    inner: io::Lines<R>,
+    number: usize,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<Line, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.inner.next()?;
+        self.number += 1;
+        Some(
+            text.map(|text| Line {
+                number: self.number,
+                text,
+            })
+            .map_err(ParseError::from),
+        )
+    }
+}
+
+pub fn lines<R: BufRead>(reader: R) -> Lines<R> {
+    Lines {
+        inner: reader.lines(),
+        number: 0,
+    }
+}
+
+/// Parse every line of a reader into `T`
+pub fn parse_lines<T: FromLine, R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<T, ParseError>> {
+    lines(reader).map(|line| line.and_then(|line| T::from_line(&line)))
+}
+
+/// Collect every item, or report every malformed one rather than only the first.
+/// Stops early on an I/O error since the reader is unlikely to recover.
+pub fn collect_all<T>(
+    items: impl Iterator<Item = Result<T, ParseError>>,
+) -> Result<Vec<T>, ParseError> {
+    let mut found: Vec<T> = Vec::new();
+    let mut errors: Vec<ParseError> = Vec::new();
+    for item in items {
+        match item {
+            Ok(item) => found.push(item),
+            Err(e @ ParseError::Io(_)) => {
+                errors.push(e);
+                break;
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(found)
+    } else {
+        Err(ParseError::combine(errors))
+    }
+}
+
+/// Groups of consecutive non-blank lines, as separated by one or more blank lines
+pub struct Paragraphs<R> {
+    lines: Lines<R>,
+}
+
+impl<R: BufRead> Iterator for Paragraphs<R> {
+    type Item = Result<Vec<Line>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut paragraph: Vec<Line> = Vec::new();
+        for line in self.lines.by_ref() {
+            match line {
+                Err(e) => return Some(Err(e)),
+                Ok(line) if line.is_blank() => {
+                    if !paragraph.is_empty() {
+                        return Some(Ok(paragraph));
+                    }
+                }
+                Ok(line) => paragraph.push(line),
+            }
+        }
+        if paragraph.is_empty() {
+            None
+        } else {
+            Some(Ok(paragraph))
+        }
+    }
+}
+
+pub fn paragraphs<R: BufRead>(reader: R) -> Paragraphs<R> {
+    Paragraphs {
+        lines: lines(reader),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_lines() {
+        let found: Vec<Line> = lines("a\nb\n".as_bytes()).map(|l| l.unwrap()).collect();
+        assert_eq!(found[0].number, 1);
+        assert_eq!(found[1].number, 2);
+        assert_eq!(found[1].text, "b");
+    }
+
+    #[test]
+    fn splits_paragraphs() {
+        let input = "1\n2\n\n3\n\n\n4\n5\n";
+        let found: Vec<Vec<usize>> = paragraphs(input.as_bytes())
+            .map(|p| p.unwrap().iter().map(|l| l.number).collect())
+            .collect();
+        assert_eq!(found, vec![vec![1, 2], vec![4], vec![7, 8]]);
+    }
+
+    #[test]
+    fn error_points_at_field() {
+        let line = Line {
+            number: 3,
+            text: "move 1 from x to 2".to_owned(),
+        };
+        let field = line.text.split_ascii_whitespace().nth(3).unwrap();
+        match line.parse::<usize>(field) {
+            Err(ParseError::Malformed { line, column, .. }) => {
+                assert_eq!((line, column), (3, 13));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_the_files_errors_already_name() {
+        let malformed = || ParseError::at_line(1, "bad");
+        let many = ParseError::Many(vec![malformed().in_file("a"), malformed().in_file("b")]);
+        assert_eq!(
+            many.in_file("default").to_string(),
+            "2 problems found\n  a: line 1, column 1: bad\n  b: line 1, column 1: bad"
+        );
+        let mixed = ParseError::Many(vec![malformed().in_file("a"), malformed()]);
+        assert!(mixed
+            .in_file("default")
+            .to_string()
+            .starts_with("default: "));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::parse::ParseError;
+use crate::{day1, day10, day11, day12, day2, day3, day4, day5, day6, day7, day8, day9};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Solver = fn(&mut dyn BufRead, u8) -> Result<String, ParseError>;
+
+// Puzzle inputs run to tens of kilobytes
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+/// How many connections are handled at once; any more wait to be accepted
+const WORKERS: usize = 8;
+
+fn solver(day: u8) -> Option<Solver> {
+    match day {
+        1 => Some(day1::solve),
+        2 => Some(day2::solve),
+        3 => Some(day3::solve),
+        4 => Some(day4::solve),
+        5 => Some(day5::solve),
+        6 => Some(day6::solve),
+        7 => Some(day7::solve),
+        8 => Some(day8::solve),
+        9 => Some(day9::solve),
+        10 => Some(day10::solve),
+        11 => Some(day11::solve),
+        12 => Some(day12::solve),
+        _ => None,
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Answer `POST /solve/{day}/{part}` requests on localhost until the process is killed,
+/// `WORKERS` at a time, dropping any connection that stalls for longer than `timeout`
+pub fn serve(port: u16, timeout: Duration) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    io::stdout().flush()?;
+    // Once every worker is busy and the queue is full, stop accepting connections
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(WORKERS);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let stream = match receiver.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            if let Err(e) = handle(stream, timeout) {
+                log::warn!("Unable to answer request: {}", e);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => sender
+                .send(stream)
+                .expect("Workers only stop once the listener does"),
+            Err(e) => log::warn!("Unable to accept connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok(request) => {
+            log::info!("{} {}", request.method, request.path);
+            route(&request)
+        }
+        Err(response) => response,
+    };
+    write_response(&stream, &response)?;
+    // Closing with part of the request unread would reset the connection, and the
+    // client could lose the response, so let it finish sending first
+    stream.shutdown(Shutdown::Write)?;
+    _ = io::copy(&mut reader.take(MAX_BODY_SIZE as u64), &mut io::sink());
+    Ok(())
+}
+
+fn bad_request(e: io::Error) -> Response {
+    match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            Response::error(408, "timed out waiting for the request")
+        }
+        _ => Response::error(400, e.to_string()),
+    }
+}
+
+/// The next line of the request head, which may only use up `remaining` more bytes
+fn read_head_line(reader: &mut impl BufRead, remaining: &mut usize) -> Result<String, Response> {
+    let mut line = String::new();
+    let read = reader
+        .take(*remaining as u64)
+        .read_line(&mut line)
+        .map_err(bad_request)?;
+    *remaining -= read;
+    if *remaining == 0 && !line.ends_with('\n') {
+        return Err(Response::error(431, "request headers are too large"));
+    }
+    Ok(line)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let mut remaining = MAX_HEADER_SIZE;
+    let request_line = read_head_line(reader, &mut remaining)?;
+    let mut parts = request_line.split_ascii_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_owned(), path.to_owned()),
+        _ => return Err(Response::error(400, "malformed request line")),
+    };
+
+    let mut content_length: Option<usize> = None;
+    loop {
+        let header = read_head_line(reader, &mut remaining)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                let length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let body = match content_length {
+        Some(length) if length > MAX_BODY_SIZE => {
+            return Err(Response::error(413, "puzzle input is too large"));
+        }
+        Some(length) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).map_err(bad_request)?;
+            body
+        }
+        None if method == "POST" => {
+            return Err(Response::error(411, "Content-Length is required"));
+        }
+        None => Vec::new(),
+    };
+    Ok(Request { method, path, body })
+}
+
+fn route(request: &Request) -> Response {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let (day, part) = match segments.as_slice() {
+        ["solve", day, part] => (day.parse::<u8>().ok(), part.parse::<u8>().ok()),
+        _ => return Response::error(404, format!("no such endpoint {}", request.path)),
+    };
+    if request.method != "POST" {
+        return Response::error(405, "puzzle input must be POSTed");
+    }
+    let (day, part, solve) = match (day, part) {
+        (Some(day), Some(part @ (1 | 2))) => match solver(day) {
+            Some(solve) => (day, part, solve),
+            None => return Response::error(404, format!("day {} is not solved", day)),
+        },
+        _ => return Response::error(404, format!("no such puzzle {}", request.path)),
+    };
+
+    let start = Instant::now();
+    let mut input: &[u8] = &request.body;
+    // Bad input should come back as an error; a panic is a bug in the solver, but one
+    // that shouldn't take the connection down with it
+    let answer = panic::catch_unwind(AssertUnwindSafe(|| solve(&mut input, part)));
+    let elapsed = start.elapsed();
+    match answer {
+        Ok(Ok(answer)) => Response {
+            status: 200,
+            body: json!({
+                "day": day,
+                "part": part,
+                "answer": answer,
+                "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+            }),
+        },
+        Ok(Err(e @ ParseError::Malformed { line, column, .. })) => Response {
+            status: 400,
+            body: json!({ "error": e.to_string(), "line": line, "column": column }),
+        },
+        Ok(Err(e)) => Response::error(400, e.to_string()),
+        Err(_) => {
+            log::error!("The day {} part {} solver panicked", day, part);
+            Response::error(500, "the solver failed on this input")
+        }
+    }
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
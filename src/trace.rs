@@ -0,0 +1,272 @@
+use crate::parse::{self, Line, ParseError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, IsTerminal, Write};
+use std::sync::{Mutex, OnceLock};
+
+/// One step of a simulation as written to, and read back from, a trace file
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Record {
+    pub day: u8,
+    pub part: u8,
+    pub step: usize,
+    pub event: String,
+    pub state: Value,
+}
+
+struct Tracer<W> {
+    day: u8,
+    part: u8,
+    step: usize,
+    out: W,
+}
+
+impl<W: Write> Tracer<W> {
+    fn record(&mut self, event: &str, state: &impl Serialize) -> io::Result<()> {
+        let state = serde_json::to_value(state).expect("Simulation state should serialise");
+        let record = Record {
+            day: self.day,
+            part: self.part,
+            step: self.step,
+            event: event.to_owned(),
+            state,
+        };
+        self.step += 1;
+        serde_json::to_writer(&mut self.out, &record)?;
+        writeln!(self.out)
+    }
+}
+
+static TRACER: OnceLock<Mutex<Tracer<BufWriter<File>>>> = OnceLock::new();
+
+/// Start recording every `record` call into `path`, one JSON object per line
+pub fn init(path: &str, day: u8, part: u8) -> io::Result<()> {
+    let tracer = Tracer {
+        day,
+        part,
+        step: 0,
+        out: BufWriter::new(File::create(path)?),
+    };
+    if TRACER.set(Mutex::new(tracer)).is_err() {
+        panic!("Tracing already initialised");
+    }
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    TRACER.get().is_some()
+}
+
+pub fn record(event: &str, state: &impl Serialize) {
+    let Some(tracer) = TRACER.get() else {
+        return;
+    };
+    if let Err(e) = tracer.lock().unwrap().record(event, state) {
+        log::error!("Unable to write trace: {}", e);
+    }
+}
+
+/// Flush anything still buffered; call once the simulation has finished
+pub fn finish() -> io::Result<()> {
+    match TRACER.get() {
+        Some(tracer) => tracer.lock().unwrap().out.flush(),
+        None => Ok(()),
+    }
+}
+
+fn read_record(line: &Line) -> Result<Record, ParseError> {
+    serde_json::from_str(&line.text)
+        .map_err(|e| line.error(e.column(), format!("invalid trace record: {}", e)))
+}
+
+fn read_records(reader: impl BufRead) -> Result<Vec<Record>, ParseError> {
+    parse::lines(reader)
+        .map(|line| line.and_then(|line| read_record(&line)))
+        .collect()
+}
+
+fn load(path: &str) -> Result<Vec<Record>, ParseError> {
+    parse::open(path)
+        .and_then(read_records)
+        .map_err(|e| e.in_file(path))
+}
+
+fn write_record(out: &mut impl Write, record: &Record) -> io::Result<()> {
+    writeln!(
+        out,
+        "day {} part {} step {}: {}",
+        record.day, record.part, record.step, record.event
+    )?;
+    match &record.state {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                writeln!(out, "  {}: {}", name, value)?;
+            }
+        }
+        other => writeln!(out, "  {}", other)?,
+    }
+    Ok(())
+}
+
+fn show(record: &Record) {
+    write_record(&mut io::stdout().lock(), record).expect("Unable to write to stdout");
+}
+
+/// Where two traces stop agreeing
+#[derive(Debug, Eq, PartialEq)]
+enum Divergence {
+    /// The records at this index differ in event or state
+    At(usize),
+    /// Every common record agrees, but one trace carries on for longer
+    Length { ours: usize, theirs: usize },
+}
+
+fn diverge(records: &[Record], others: &[Record]) -> Option<Divergence> {
+    let differs = |(record, other): &(&Record, &Record)| {
+        record.event != other.event || record.state != other.state
+    };
+    if let Some(idx) = records.iter().zip(others).position(|pair| differs(&pair)) {
+        Some(Divergence::At(idx))
+    } else if records.len() != others.len() {
+        Some(Divergence::Length {
+            ours: records.len(),
+            theirs: others.len(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Show the records of a trace one at a time. On a terminal, wait between steps
+/// for enter (next), a step number (jump) or `q` (quit).
+pub fn replay(path: &str) -> Result<(), ParseError> {
+    let records = load(path)?;
+    let interactive = io::stdin().is_terminal();
+    let mut idx: usize = 0;
+    while idx < records.len() {
+        show(&records[idx]);
+        idx += 1;
+        if !interactive || idx == records.len() {
+            continue;
+        }
+        print!("[enter] next, [N] jump to step N, [q] quit > ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            break;
+        }
+        match answer.trim() {
+            "" => {}
+            "q" => break,
+            n => match n.parse::<usize>() {
+                Ok(step) => match records.iter().position(|r| r.step == step) {
+                    Some(found) => idx = found,
+                    None => println!("No step {} in this trace", step),
+                },
+                Err(_) => println!("Unrecognised command `{}`", n),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Walk two traces side by side and report the first step at which they disagree
+pub fn compare(path: &str, other_path: &str) -> Result<(), ParseError> {
+    let records = load(path)?;
+    let others = load(other_path)?;
+    match diverge(&records, &others) {
+        Some(Divergence::At(idx)) => {
+            println!("Traces diverge at step {}", records[idx].step);
+            println!("{}:", path);
+            show(&records[idx]);
+            println!("{}:", other_path);
+            show(&others[idx]);
+        }
+        Some(Divergence::Length { ours, theirs }) => println!(
+            "Traces agree for {} steps, but {} has {} and {} has {}",
+            ours.min(theirs),
+            path,
+            ours,
+            other_path,
+            theirs
+        ),
+        None => println!("Traces are identical ({} steps)", records.len()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Record `xs` as successive steps and read the trace back
+    fn traced(xs: &[i32]) -> Vec<Record> {
+        let mut tracer = Tracer {
+            day: 10,
+            part: 1,
+            step: 0,
+            out: Vec::new(),
+        };
+        for x in xs {
+            tracer.record("noop", &json!({ "x": x })).unwrap();
+        }
+        read_records(&tracer.out[..]).unwrap()
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let records = traced(&[1, 3]);
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    day: 10,
+                    part: 1,
+                    step: 0,
+                    event: "noop".to_owned(),
+                    state: json!({ "x": 1 }),
+                },
+                Record {
+                    day: 10,
+                    part: 1,
+                    step: 1,
+                    event: "noop".to_owned(),
+                    state: json!({ "x": 3 }),
+                },
+            ]
+        );
+
+        let mut shown = Vec::new();
+        write_record(&mut shown, &records[1]).unwrap();
+        assert_eq!(
+            String::from_utf8(shown).unwrap(),
+            "day 10 part 1 step 1: noop\n  x: 3\n"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_records() {
+        let err = read_records(&b"{\"day\": 1}\nnot json\n"[..]).unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{}", err);
+    }
+
+    #[test]
+    fn finds_where_traces_diverge() {
+        assert_eq!(diverge(&traced(&[1, 2, 3]), &traced(&[1, 2, 3])), None);
+        assert_eq!(
+            diverge(&traced(&[1, 2, 3, 4]), &traced(&[1, 2, 5, 4])),
+            Some(Divergence::At(2))
+        );
+        assert_eq!(
+            diverge(&traced(&[1, 2]), &traced(&[1, 2, 3])),
+            Some(Divergence::Length { ours: 2, theirs: 3 })
+        );
+        // A difference wins over a length mismatch after it
+        assert_eq!(
+            diverge(&traced(&[0, 2]), &traced(&[1, 2, 3])),
+            Some(Divergence::At(0))
+        );
+    }
+}
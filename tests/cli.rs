@@ -0,0 +1,25 @@
+use std::process::Command;
+
+/// The exit code and error message from running with `args`
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_advent2022"))
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (output.status.code(), stderr)
+}
+
+#[test]
+fn rejects_flags_for_other_days() {
+    let (code, stderr) = run(&["-d", "1", "-p", "1", "--crane", "two-arm"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("'--crane' only applies to day 5"), "{}", stderr);
+    let (code, stderr) = run(&["-d", "1", "-p", "2", "--misplaced"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("'--misplaced' only applies to day 3"), "{}", stderr);
+    let (code, stderr) = run(&["-d", "3", "-p", "2", "--misplaced"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("only applies to part 1"), "{}", stderr);
+}
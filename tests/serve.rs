@@ -0,0 +1,145 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// A `serve` process on an ephemeral port, killed when dropped
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start() -> Self {
+        Server::start_with(&[])
+    }
+
+    fn start_with(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_advent2022"))
+            .args(["serve", "--port", "0"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Unable to start server");
+        let mut banner = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut banner)
+            .unwrap();
+        let addr = banner
+            .trim()
+            .strip_prefix("Listening on http://")
+            .expect("Unexpected banner")
+            .to_owned();
+        Server { child, addr }
+    }
+
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, body.to_owned())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+#[test]
+fn solves_posted_input() {
+    let server = Server::start();
+    let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+    let (status, body) = server.request("POST", "/solve/1/2", input);
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""answer":"45000""#), "{}", body);
+    assert!(body.contains(r#""elapsed_ms":"#), "{}", body);
+}
+
+#[test]
+fn malformed_input_is_a_client_error() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/solve/1/1", "1000\n12a4\n");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""line":2"#), "{}", body);
+}
+
+#[test]
+fn unsolvable_input_is_a_client_error() {
+    let server = Server::start();
+    // a monkey throwing to monkeys that don't exist
+    let monkey = "Monkey 0:\n  Starting items: 79\n  Operation: new = old * 19\n  Test: divisible by 23\n    If true: throw to monkey 2\n    If false: throw to monkey 3\n";
+    let (status, body) = server.request("POST", "/solve/11/1", monkey);
+    assert_eq!(status, 400);
+    assert!(body.contains("there is no monkey 2"), "{}", body);
+    // a program too short to reach the cycles part 1 samples
+    let (status, body) = server.request("POST", "/solve/10/1", "noop\n");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""line":1"#), "{}", body);
+    // more moves than a request may make
+    let (status, body) = server.request("POST", "/solve/9/1", "R 1000000000000\n");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""line":1"#), "{}", body);
+    // totals too large to add up
+    let (status, body) = server.request("POST", "/solve/1/1", "18446744073709551615\n1\n");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""line":2"#), "{}", body);
+    let (status, body) =
+        server.request("POST", "/solve/10/2", "addx 9223372036854775806\naddx 1\n");
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""line":2"#), "{}", body);
+}
+
+#[test]
+fn stalled_requests_time_out() {
+    let server = Server::start_with(&["--timeout", "1"]);
+    let mut stream = TcpStream::connect(&server.addr).unwrap();
+    // promise more of the body than is ever sent
+    write!(
+        stream,
+        "POST /solve/1/1 HTTP/1.1\r\nContent-Length: 10\r\n\r\n100"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 408 "), "{}", response);
+    // other clients are still answered
+    assert_eq!(server.request("POST", "/solve/2/1", "A Y\n").0, 200);
+}
+
+#[test]
+fn unknown_routes() {
+    let server = Server::start();
+    assert_eq!(server.request("POST", "/solve/25/1", "").0, 404);
+    assert_eq!(server.request("POST", "/solve/1/3", "").0, 404);
+    assert_eq!(server.request("GET", "/solve/1/1", "").0, 405);
+    assert_eq!(server.request("POST", "/elsewhere", "").0, 404);
+}
+
+#[test]
+fn oversized_headers_are_refused() {
+    let server = Server::start();
+    let mut stream = TcpStream::connect(&server.addr).unwrap();
+    write!(
+        stream,
+        "POST /solve/1/1 HTTP/1.1\r\nX-Padding: {}\r\n",
+        "a".repeat(64 * 1024)
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 431 "), "{}", response);
+}
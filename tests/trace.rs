@@ -0,0 +1,32 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// A scratch directory laid out like the repository, with `input` as the day 5 puzzle
+fn workspace(name: &str, input: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("advent2022-{}-{}", name, std::process::id()));
+    fs::create_dir_all(dir.join("static")).unwrap();
+    fs::write(dir.join("static/day5-input.txt"), input).unwrap();
+    dir
+}
+
+#[test]
+fn failed_runs_keep_their_trace() {
+    // the third move asks for more crates than stack 2 still holds
+    let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 5 from 2 to 1\n";
+    let dir = workspace("failed-trace", input);
+    let status = Command::new(env!("CARGO_BIN_EXE_advent2022"))
+        .args(["-d", "5", "-p", "1", "--trace", "t.jsonl"])
+        .current_dir(&dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    let trace = fs::read_to_string(dir.join("t.jsonl")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let events: Vec<&str> = trace.lines().collect();
+    // the setup and both moves made before the bad one
+    assert_eq!(events.len(), 3, "{}", trace);
+    assert!(events[2].contains("move 3 from 1 to 3"), "{}", trace);
+}